@@ -0,0 +1,587 @@
+#[cfg(unix)]
+use std::os::unix::fs::PermissionsExt;
+use std::{
+    cell::Cell,
+    collections::{hash_map::DefaultHasher, BTreeMap, HashMap},
+    fs::{self, File},
+    hash::{Hash, Hasher},
+    io::{self, BufReader, Error, ErrorKind, Read, Result, Write},
+    path::{Component, Path, PathBuf},
+    rc::Rc,
+    sync::{Arc, Mutex},
+};
+
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use sevenz_rust::{Password, SevenZArchiveEntry, SevenZReader, SevenZWriter};
+use zip::{write::SimpleFileOptions, ZipArchive, ZipWriter};
+
+use crate::{constant::cache_file, explorer::FileStruct};
+
+static MOUNTS: Mutex<Vec<(PathBuf, PathBuf)>> = Mutex::new(Vec::new());
+
+/// Placeholders of mounted entries that haven't been extracted yet, mapped to their
+/// archive and entry name.
+static PENDING: Mutex<BTreeMap<PathBuf, (PathBuf, String)>> = Mutex::new(BTreeMap::new());
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ArchiveKind {
+    Zip,
+    Tar,
+    TarGz,
+    TarZst,
+    SevenZ,
+}
+
+const SUFFIXES: [(&str, ArchiveKind); 7] = [
+    (".tar.gz", ArchiveKind::TarGz),
+    (".tgz", ArchiveKind::TarGz),
+    (".tar.zst", ArchiveKind::TarZst),
+    (".tzst", ArchiveKind::TarZst),
+    (".tar", ArchiveKind::Tar),
+    (".zip", ArchiveKind::Zip),
+    (".7z", ArchiveKind::SevenZ),
+];
+
+impl ArchiveKind {
+    pub fn of(path: &Path) -> Option<Self> {
+        split_name(path).map(|(_, kind)| kind)
+    }
+}
+
+fn stem(path: &Path) -> Option<String> {
+    split_name(path).map(|(stem, _)| stem)
+}
+
+fn split_name(path: &Path) -> Option<(String, ArchiveKind)> {
+    let name = path.file_name()?.to_str()?;
+    let lower = name.to_lowercase();
+    SUFFIXES
+        .iter()
+        .find(|(suffix, _)| lower.ends_with(suffix) && lower.len() > suffix.len())
+        .map(|(suffix, kind)| (name[..name.len() - suffix.len()].to_string(), *kind))
+}
+
+fn unsupported() -> Error {
+    Error::new(
+        ErrorKind::Unsupported,
+        "unsupported archive, use .zip, .tar, .tar.gz, .tar.zst or .7z",
+    )
+}
+
+pub struct Progress {
+    pub label: String,
+    pub done: u64,
+    pub total: u64,
+}
+
+impl Progress {
+    pub fn new(label: String) -> Self {
+        Self {
+            label,
+            done: 0,
+            total: 0,
+        }
+    }
+
+    pub fn percent(&self) -> u64 {
+        (self.done * 100).checked_div(self.total).unwrap_or(0)
+    }
+}
+
+pub type ArchiveTask = Arc<Mutex<Option<Progress>>>;
+
+fn report(task: &Mutex<Option<Progress>>, done: u64, total: u64) {
+    if let Some(progress) = task.lock().unwrap().as_mut() {
+        progress.done = done;
+        progress.total = total;
+    }
+}
+
+struct Listing {
+    name: String,
+    path: PathBuf,
+    is_dir: bool,
+    size: u64,
+}
+
+/// Lays out the archive's entries as directories and sparse placeholder files, the
+/// contents are extracted by [`materialize`] when something reads them.
+pub fn mount(archive: &Path, task: &Mutex<Option<Progress>>) -> Result<PathBuf> {
+    if let Some((_, root)) = MOUNTS
+        .lock()
+        .unwrap()
+        .iter()
+        .find(|(mounted, root)| mounted == archive && root.is_dir())
+    {
+        return Ok(root.to_path_buf());
+    }
+    materialize(&[archive.to_path_buf()])?;
+
+    let metadata = fs::metadata(archive)?;
+    let mut hasher = DefaultHasher::new();
+    archive.hash(&mut hasher);
+    metadata.len().hash(&mut hasher);
+    metadata.modified().ok().hash(&mut hasher);
+    let dir = cache_file("archives")
+        .ok_or_else(|| Error::new(ErrorKind::NotFound, "no cache directory"))?
+        .join(format!("{:016x}", hasher.finish()));
+    let name = archive
+        .file_name()
+        .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "invalid file name"))?;
+
+    if dir.exists() {
+        fs::remove_dir_all(&dir)?;
+    }
+    fs::create_dir_all(dir.join(name))?;
+    let root = fs::canonicalize(dir.join(name))?;
+    let mut placeholders = Vec::new();
+    let laid_out = list(archive, task).and_then(|entries| {
+        for entry in entries {
+            let target = root.join(&entry.path);
+            if entry.is_dir {
+                fs::create_dir_all(&target)?;
+                continue;
+            }
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            File::create(&target)?.set_len(entry.size)?;
+            placeholders.push((target, (archive.to_path_buf(), entry.name)));
+        }
+        Ok(())
+    });
+    if let Err(error) = laid_out {
+        let _ = fs::remove_dir_all(&dir);
+        return Err(error);
+    }
+
+    PENDING.lock().unwrap().extend(placeholders);
+    MOUNTS
+        .lock()
+        .unwrap()
+        .push((archive.to_path_buf(), root.to_path_buf()));
+    Ok(root)
+}
+
+fn list(archive: &Path, task: &Mutex<Option<Progress>>) -> Result<Vec<Listing>> {
+    let mut entries = Vec::new();
+    match ArchiveKind::of(archive).ok_or_else(unsupported)? {
+        ArchiveKind::Zip => {
+            let mut zip = ZipArchive::new(BufReader::new(File::open(archive)?))?;
+            let total = zip.len() as u64;
+            for index in 0..zip.len() {
+                let entry = zip.by_index_raw(index)?;
+                if let Some(path) = entry.enclosed_name() {
+                    entries.push(Listing {
+                        name: entry.name()?.to_string(),
+                        path,
+                        is_dir: entry.is_dir(),
+                        size: entry.size(),
+                    });
+                }
+                report(task, index as u64 + 1, total);
+            }
+        }
+        ArchiveKind::SevenZ => {
+            let files = sevenz_rust::Archive::open(archive)
+                .map_err(Error::other)?
+                .files;
+            for entry in files {
+                if let Some(path) = enclosed(entry.name()) {
+                    entries.push(Listing {
+                        name: entry.name().to_string(),
+                        path,
+                        is_dir: entry.is_directory(),
+                        size: entry.size(),
+                    });
+                }
+            }
+        }
+        kind => {
+            let total = fs::metadata(archive)?.len();
+            let (mut tar, read) = open_tar(kind, archive)?;
+            for entry in tar.entries()? {
+                let entry = entry?;
+                let name = entry.path()?.to_string_lossy().to_string();
+                if let Some(path) = enclosed(&name) {
+                    entries.push(Listing {
+                        is_dir: entry.header().entry_type().is_dir(),
+                        size: entry.size(),
+                        name,
+                        path,
+                    });
+                }
+                report(task, read.get(), total);
+            }
+        }
+    }
+    Ok(entries)
+}
+
+fn enclosed(name: &str) -> Option<PathBuf> {
+    let path = Path::new(name);
+    path.components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+        .then(|| path.components().collect::<PathBuf>())
+        .filter(|path| !path.as_os_str().is_empty())
+}
+
+/// Extracts the pending archive entries at or below `paths`.
+pub fn materialize(paths: &[PathBuf]) -> Result<()> {
+    let mut wanted: HashMap<PathBuf, HashMap<String, PathBuf>> = HashMap::new();
+    {
+        let pending = PENDING.lock().unwrap();
+        for path in paths {
+            for (target, (archive, name)) in pending
+                .range(path.to_path_buf()..)
+                .take_while(|(target, _)| target.starts_with(path))
+            {
+                wanted
+                    .entry(archive.to_path_buf())
+                    .or_default()
+                    .insert(name.to_string(), target.to_path_buf());
+            }
+        }
+    }
+    for (archive, entries) in wanted {
+        let targets: Vec<PathBuf> = entries.values().cloned().collect();
+        extract_entries(&archive, entries)?;
+        let mut pending = PENDING.lock().unwrap();
+        for target in targets {
+            pending.remove(&target);
+        }
+    }
+    Ok(())
+}
+
+fn extract_entries(archive: &Path, mut entries: HashMap<String, PathBuf>) -> Result<()> {
+    match ArchiveKind::of(archive).ok_or_else(unsupported)? {
+        ArchiveKind::Zip => {
+            let mut zip = ZipArchive::new(BufReader::new(File::open(archive)?))?;
+            for (name, target) in entries {
+                let mut entry = zip.by_name(&name)?;
+                io::copy(&mut entry, &mut File::create(&target)?)?;
+                #[cfg(unix)]
+                if let Some(mode) = entry.unix_mode() {
+                    fs::set_permissions(&target, fs::Permissions::from_mode(mode & 0o777))?;
+                }
+            }
+        }
+        ArchiveKind::SevenZ => {
+            let mut reader =
+                SevenZReader::open(archive, Password::empty()).map_err(Error::other)?;
+            reader
+                .for_each_entries(|entry, data| {
+                    match entries.remove(entry.name()) {
+                        Some(target) => io::copy(data, &mut File::create(target)?)?,
+                        // Solid blocks have to be decoded in order, so skipped entries are drained.
+                        None => io::copy(data, &mut io::sink())?,
+                    };
+                    Ok(!entries.is_empty())
+                })
+                .map_err(Error::other)?;
+        }
+        kind => {
+            let (mut tar, _) = open_tar(kind, archive)?;
+            for entry in tar.entries()? {
+                let mut entry = entry?;
+                let name = entry.path()?.to_string_lossy().to_string();
+                let Some(target) = entries.remove(&name) else {
+                    continue;
+                };
+                // Links could point anywhere on the host, so their placeholders stay empty.
+                if entry.header().entry_type().is_file() {
+                    fs::remove_file(&target)?;
+                    entry.unpack(&target)?;
+                }
+                if entries.is_empty() {
+                    break;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+pub fn mount_of(path: &Path) -> Option<(PathBuf, PathBuf)> {
+    MOUNTS
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(_, root)| path.starts_with(root))
+        .max_by_key(|(_, root)| root.components().count())
+        .cloned()
+}
+
+pub fn entry_of(path: &Path) -> PathBuf {
+    match mount_of(path) {
+        Some((archive, root)) if root == path => archive,
+        _ => path.to_path_buf(),
+    }
+}
+
+pub fn host_of(path: &Path) -> PathBuf {
+    match mount_of(path) {
+        Some((archive, _)) => host_of(&archive),
+        None => path.to_path_buf(),
+    }
+}
+
+pub fn display_path(path: &Path) -> PathBuf {
+    match mount_of(path) {
+        Some((archive, root)) => {
+            let inner = path.strip_prefix(&root).unwrap_or(path);
+            let archive = display_path(&archive);
+            if inner.as_os_str().is_empty() {
+                archive
+            } else {
+                archive.join(inner)
+            }
+        }
+        None => path.to_path_buf(),
+    }
+}
+
+pub fn clear_mounts() {
+    PENDING.lock().unwrap().clear();
+    for (_, root) in MOUNTS.lock().unwrap().drain(..) {
+        if let Some(dir) = root.parent() {
+            let _ = fs::remove_dir_all(dir);
+        }
+    }
+}
+
+pub fn extract(archive: &Path, dest: &Path, task: &Mutex<Option<Progress>>) -> Result<()> {
+    match ArchiveKind::of(archive).ok_or_else(unsupported)? {
+        ArchiveKind::Zip => extract_zip(archive, dest, task),
+        ArchiveKind::SevenZ => extract_7z(archive, dest, task),
+        kind => extract_tar(kind, archive, dest, task),
+    }
+}
+
+pub fn extract_here(archive: &Path, task: &Mutex<Option<Progress>>) -> Result<()> {
+    let (Some(parent), Some(stem)) = (archive.parent(), stem(archive)) else {
+        return Err(unsupported());
+    };
+    let dest = parent.join(stem);
+    if dest.exists() {
+        return Err(Error::new(
+            ErrorKind::AlreadyExists,
+            format!("{} already exists", dest.display()),
+        ));
+    }
+    fs::create_dir(&dest)?;
+    extract(archive, &dest, task).inspect_err(|_| {
+        let _ = fs::remove_dir_all(&dest);
+    })
+}
+
+pub fn copy_out(paths: &[PathBuf], dest: &Path, task: &Mutex<Option<Progress>>) -> Result<()> {
+    materialize(paths)?;
+    for (index, path) in paths.iter().enumerate() {
+        FileStruct::copy_to(path, dest)?;
+        report(task, index as u64 + 1, paths.len() as u64);
+    }
+    Ok(())
+}
+
+fn extract_zip(archive: &Path, dest: &Path, task: &Mutex<Option<Progress>>) -> Result<()> {
+    let mut zip = ZipArchive::new(BufReader::new(File::open(archive)?))?;
+    let total = zip.len() as u64;
+    for index in 0..zip.len() {
+        let mut entry = zip.by_index(index)?;
+        let Some(name) = entry.enclosed_name() else {
+            continue;
+        };
+        let target = dest.join(name);
+        if entry.is_dir() {
+            fs::create_dir_all(&target)?;
+        } else {
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            io::copy(&mut entry, &mut File::create(&target)?)?;
+            #[cfg(unix)]
+            if let Some(mode) = entry.unix_mode() {
+                fs::set_permissions(&target, fs::Permissions::from_mode(mode & 0o777))?;
+            }
+        }
+        report(task, index as u64 + 1, total);
+    }
+    Ok(())
+}
+
+fn extract_7z(archive: &Path, dest: &Path, task: &Mutex<Option<Progress>>) -> Result<()> {
+    let total = sevenz_rust::Archive::open(archive)
+        .map_err(Error::other)?
+        .files
+        .len() as u64;
+    let mut done = 0;
+    sevenz_rust::decompress_file_with_extract_fn(archive, dest, |entry, reader, target| {
+        done += 1;
+        report(task, done, total);
+        let enclosed = Path::new(entry.name())
+            .components()
+            .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+        if !enclosed {
+            return Ok(true);
+        }
+        sevenz_rust::default_entry_extract_fn(entry, reader, target)
+    })
+    .map_err(Error::other)
+}
+
+struct CountingReader<R> {
+    inner: R,
+    read: Rc<Cell<u64>>,
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.read.set(self.read.get() + read as u64);
+        Ok(read)
+    }
+}
+
+type TarReader = tar::Archive<Box<dyn Read>>;
+
+fn open_tar(kind: ArchiveKind, archive: &Path) -> Result<(TarReader, Rc<Cell<u64>>)> {
+    let read = Rc::new(Cell::new(0));
+    let reader = CountingReader {
+        inner: BufReader::new(File::open(archive)?),
+        read: Rc::clone(&read),
+    };
+    let reader: Box<dyn Read> = match kind {
+        ArchiveKind::TarGz => Box::new(GzDecoder::new(reader)),
+        ArchiveKind::TarZst => Box::new(zstd::Decoder::new(reader)?),
+        _ => Box::new(reader),
+    };
+    Ok((tar::Archive::new(reader), read))
+}
+
+fn extract_tar(
+    kind: ArchiveKind,
+    archive: &Path,
+    dest: &Path,
+    task: &Mutex<Option<Progress>>,
+) -> Result<()> {
+    let total = fs::metadata(archive)?.len();
+    let (mut tar, read) = open_tar(kind, archive)?;
+    for entry in tar.entries()? {
+        entry?.unpack_in(dest)?;
+        report(task, read.get(), total);
+    }
+    Ok(())
+}
+
+pub fn create(dest: &Path, paths: &[PathBuf], task: &Mutex<Option<Progress>>) -> Result<()> {
+    let kind = ArchiveKind::of(dest).ok_or_else(unsupported)?;
+    let mut entries = Vec::new();
+    for path in paths {
+        if let Some(name) = path.file_name() {
+            collect(path, PathBuf::from(name), &mut entries)?;
+        }
+    }
+
+    let file = File::create_new(dest)?;
+    let result = write_archive(kind, file, &entries, task);
+    if result.is_err() {
+        let _ = fs::remove_file(dest);
+    }
+    result
+}
+
+fn collect(path: &Path, name: PathBuf, entries: &mut Vec<(PathBuf, PathBuf)>) -> Result<()> {
+    entries.push((path.to_path_buf(), name.to_path_buf()));
+    if fs::symlink_metadata(path)?.is_dir() {
+        for entry in fs::read_dir(path)? {
+            let entry = entry?;
+            collect(&entry.path(), name.join(entry.file_name()), entries)?;
+        }
+    }
+    Ok(())
+}
+
+fn entry_name(name: &Path) -> String {
+    name.components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn write_archive(
+    kind: ArchiveKind,
+    file: File,
+    entries: &[(PathBuf, PathBuf)],
+    task: &Mutex<Option<Progress>>,
+) -> Result<()> {
+    let total = entries.len() as u64;
+    match kind {
+        ArchiveKind::Zip => {
+            let mut zip = ZipWriter::new(file);
+            for (index, (path, name)) in entries.iter().enumerate() {
+                let metadata = fs::symlink_metadata(path)?;
+                let options = SimpleFileOptions::default();
+                #[cfg(unix)]
+                let options = options.unix_permissions(metadata.permissions().mode());
+                if metadata.is_symlink() {
+                    let target = fs::read_link(path)?;
+                    zip.add_symlink(entry_name(name), target.to_string_lossy(), options)?;
+                } else if metadata.is_dir() {
+                    zip.add_directory(entry_name(name), options)?;
+                } else {
+                    zip.start_file(entry_name(name), options)?;
+                    io::copy(&mut File::open(path)?, &mut zip)?;
+                }
+                report(task, index as u64 + 1, total);
+            }
+            zip.finish()?;
+        }
+        ArchiveKind::Tar => {
+            write_tar(file, entries, task)?;
+        }
+        ArchiveKind::TarGz => {
+            write_tar(GzEncoder::new(file, Compression::default()), entries, task)?.finish()?;
+        }
+        ArchiveKind::TarZst => {
+            write_tar(zstd::Encoder::new(file, 0)?, entries, task)?.finish()?;
+        }
+        ArchiveKind::SevenZ => {
+            let mut writer = SevenZWriter::new(file).map_err(Error::other)?;
+            for (index, (path, name)) in entries.iter().enumerate() {
+                report(task, index as u64 + 1, total);
+                // 7z has no symlink entries, and links are never followed.
+                let metadata = fs::symlink_metadata(path)?;
+                if metadata.is_symlink() {
+                    continue;
+                }
+                let entry = SevenZArchiveEntry::from_path(path, entry_name(name));
+                let reader = if metadata.is_dir() {
+                    None
+                } else {
+                    Some(File::open(path)?)
+                };
+                writer
+                    .push_archive_entry(entry, reader)
+                    .map_err(Error::other)?;
+            }
+            writer.finish()?;
+        }
+    }
+    Ok(())
+}
+
+fn write_tar<W: Write>(
+    writer: W,
+    entries: &[(PathBuf, PathBuf)],
+    task: &Mutex<Option<Progress>>,
+) -> Result<W> {
+    let mut tar = tar::Builder::new(writer);
+    tar.follow_symlinks(false);
+    for (index, (path, name)) in entries.iter().enumerate() {
+        tar.append_path_with_name(path, name)?;
+        report(task, index as u64 + 1, entries.len() as u64);
+    }
+    tar.into_inner()
+}
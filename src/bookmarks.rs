@@ -0,0 +1,153 @@
+use std::{
+    fs,
+    io::{Error, ErrorKind, Result},
+    path::{Path, PathBuf},
+};
+
+use ratatui::widgets::ListState;
+
+use crate::constant::data_file;
+
+const BOOKMARKS_FILE: &str = "bookmarks";
+const ESCAPES: [(char, &str); 4] = [('%', "%25"), ('\t', "%09"), ('\n', "%0A"), ('\r', "%0D")];
+
+pub struct Bookmark {
+    pub key: char,
+    pub name: String,
+    pub path: PathBuf,
+}
+
+impl Bookmark {
+    pub fn is_broken(&self) -> bool {
+        !self.path.is_dir()
+    }
+}
+
+#[derive(Default)]
+pub struct Bookmarks {
+    pub entries: Vec<Bookmark>,
+    pub state: ListState,
+}
+
+impl Bookmarks {
+    pub fn load() -> Self {
+        let mut bookmarks = Bookmarks::default();
+        let Some(content) =
+            data_file(BOOKMARKS_FILE).and_then(|path| fs::read_to_string(path).ok())
+        else {
+            return bookmarks;
+        };
+        for line in content.lines() {
+            let mut fields = line.splitn(3, '\t');
+            let (Some(key), Some(name), Some(path)) = (fields.next(), fields.next(), fields.next())
+            else {
+                continue;
+            };
+            if let Some(key) = key.chars().next() {
+                bookmarks.entries.push(Bookmark {
+                    key,
+                    name: unescape(name),
+                    path: PathBuf::from(unescape(path)),
+                });
+            }
+        }
+        bookmarks
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = data_file(BOOKMARKS_FILE)
+            .ok_or_else(|| Error::new(ErrorKind::NotFound, "no data directory"))?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let content: String = self
+            .entries
+            .iter()
+            .map(|bookmark| {
+                format!(
+                    "{}\t{}\t{}\n",
+                    bookmark.key,
+                    escape(&bookmark.name),
+                    escape(&bookmark.path.to_string_lossy())
+                )
+            })
+            .collect();
+        fs::write(path, content)
+    }
+
+    pub fn get(&self, key: char) -> Option<&Bookmark> {
+        self.entries.iter().find(|bookmark| bookmark.key == key)
+    }
+
+    pub fn set(&mut self, key: char, path: &Path) -> Result<()> {
+        // Re-bookmarking a path keeps the name the user gave it.
+        let name = match self.entries.iter().find(|bookmark| bookmark.path == path) {
+            Some(bookmark) => bookmark.name.to_string(),
+            None => path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_else(|| path.to_string_lossy().to_string()),
+        };
+        match self.entries.iter_mut().find(|bookmark| bookmark.key == key) {
+            Some(bookmark) => {
+                bookmark.name = name;
+                bookmark.path = path.to_path_buf();
+            }
+            None => {
+                self.entries.push(Bookmark {
+                    key,
+                    name,
+                    path: path.to_path_buf(),
+                });
+                self.entries.sort_by_key(|bookmark| bookmark.key);
+            }
+        }
+        self.save()
+    }
+
+    pub fn rename(&mut self, index: usize, name: &str) -> Result<()> {
+        if let Some(bookmark) = self.entries.get_mut(index) {
+            bookmark.name = name.to_string();
+        }
+        self.save()
+    }
+
+    pub fn remove(&mut self, index: usize) -> Result<()> {
+        if index < self.entries.len() {
+            self.entries.remove(index);
+        }
+        self.save()
+    }
+}
+
+fn escape(field: &str) -> String {
+    let mut escaped = String::with_capacity(field.len());
+    for ch in field.chars() {
+        match ESCAPES.iter().find(|(raw, _)| *raw == ch) {
+            Some((_, code)) => escaped.push_str(code),
+            None => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+fn unescape(field: &str) -> String {
+    let mut unescaped = String::with_capacity(field.len());
+    let mut rest = field;
+    while let Some(start) = rest.find('%') {
+        unescaped.push_str(&rest[..start]);
+        rest = &rest[start..];
+        match ESCAPES.iter().find(|(_, code)| rest.starts_with(code)) {
+            Some((raw, code)) => {
+                unescaped.push(*raw);
+                rest = &rest[code.len()..];
+            }
+            None => {
+                unescaped.push('%');
+                rest = &rest[1..];
+            }
+        }
+    }
+    unescaped.push_str(rest);
+    unescaped
+}
@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+
 use ratatui::style::Color;
 
 pub const COLORS: [(Color, Color); 9] = [
@@ -13,3 +15,17 @@ pub const COLORS: [(Color, Color); 9] = [
 ];
 
 pub const KEY: &str = "mY2$3GzK!7a9VbQjL1^XwFc%rLp0tZde";
+
+pub const APP_DIR: &str = "filescout";
+
+pub fn data_file(name: &str) -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join(APP_DIR).join(name))
+}
+
+pub fn cache_file(name: &str) -> Option<PathBuf> {
+    dirs::cache_dir().map(|dir| dir.join(APP_DIR).join(name))
+}
+
+pub fn config_file(name: &str) -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join(APP_DIR).join(name))
+}
@@ -0,0 +1,241 @@
+use std::{
+    cmp::Reverse,
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::SystemTime,
+};
+
+use ratatui::widgets::ListState;
+use tokio::sync::mpsc::Sender;
+
+use crate::explorer::FileStruct;
+
+pub fn human_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+/// Measured directory sizes with the directory's mtime at the time, the size is `None` when
+/// the directory couldn't be read. Watcher events drop entries right away; the mtime catches
+/// changes the watcher doesn't see, when the directory is requested or measured again.
+type Cache = HashMap<PathBuf, (Option<SystemTime>, Option<u64>)>;
+
+#[derive(Default)]
+pub struct DirSizes {
+    cache: Arc<Mutex<Cache>>,
+    pending: Arc<Mutex<Option<PathBuf>>>,
+    cancel: Arc<AtomicBool>,
+    checked: Option<PathBuf>,
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+impl DirSizes {
+    pub fn get(&self, path: &Path) -> Option<u64> {
+        self.cache
+            .lock()
+            .unwrap()
+            .get(path)
+            .and_then(|(_, size)| *size)
+    }
+
+    pub fn is_pending(&self) -> bool {
+        self.pending.lock().unwrap().is_some()
+    }
+
+    /// Drops `path` and everything whose total includes it.
+    pub fn forget(&mut self, path: &Path) {
+        let mut cache = self.cache.lock().unwrap();
+        for ancestor in path.ancestors() {
+            cache.remove(ancestor);
+        }
+    }
+
+    /// Drops `root`, its ancestors and every directory below it, so the next request rescans.
+    pub fn rescan(&mut self, root: &Path) {
+        self.forget(root);
+        self.cache
+            .lock()
+            .unwrap()
+            .retain(|path, _| !path.starts_with(root));
+        self.cancel.store(true, Ordering::Relaxed);
+        *self.pending.lock().unwrap() = None;
+    }
+
+    pub fn sync(&mut self, files: &Arc<Mutex<FileStruct>>, tx: &Sender<String>) {
+        let selected = {
+            let file_struct = files.lock().unwrap();
+            file_struct
+                .current_path
+                .clone()
+                .filter(|path| path.is_dir())
+        };
+        if let Some(path) = selected {
+            self.request(&path, tx);
+        }
+    }
+
+    pub fn request(&mut self, path: &Path, tx: &Sender<String>) {
+        // Checked once per newly requested path rather than on every tick.
+        if self.checked.as_deref() != Some(path) {
+            self.checked = Some(path.to_path_buf());
+            let stale = self
+                .cache
+                .lock()
+                .unwrap()
+                .get(path)
+                .is_some_and(|(mtime, _)| *mtime != modified(path));
+            if stale {
+                self.forget(path);
+            }
+        }
+        if self.cache.lock().unwrap().contains_key(path) {
+            return;
+        }
+        let mut pending = self.pending.lock().unwrap();
+        if pending.as_deref() == Some(path) {
+            return;
+        }
+        self.cancel.store(true, Ordering::Relaxed);
+        self.cancel = Arc::new(AtomicBool::new(false));
+        *pending = Some(path.to_path_buf());
+
+        let path = path.to_path_buf();
+        let cache = Arc::clone(&self.cache);
+        let pending = Arc::clone(&self.pending);
+        let cancel = Arc::clone(&self.cancel);
+        let tx = tx.clone();
+        thread::spawn(move || {
+            measure(&path, modified(&path), &cancel, &cache);
+            if cancel.load(Ordering::Relaxed) {
+                return;
+            }
+            let mut pending = pending.lock().unwrap();
+            if pending.as_deref() == Some(path.as_path()) {
+                *pending = None;
+            }
+            let _ = tx.try_send(String::new());
+        });
+    }
+}
+
+fn measure(
+    path: &Path,
+    mtime: Option<SystemTime>,
+    cancel: &AtomicBool,
+    cache: &Mutex<Cache>,
+) -> Option<u64> {
+    if cancel.load(Ordering::Relaxed) {
+        return None;
+    }
+    match cache.lock().unwrap().get(path) {
+        Some((cached, size)) if *cached == mtime => return Some(size.unwrap_or_default()),
+        _ => {}
+    }
+
+    let Ok(entries) = fs::read_dir(path) else {
+        cache
+            .lock()
+            .unwrap()
+            .insert(path.to_path_buf(), (mtime, None));
+        return Some(0);
+    };
+    let mut total = 0;
+    for entry in entries.flatten() {
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        total += if metadata.is_dir() {
+            measure(&entry.path(), metadata.modified().ok(), cancel, cache)?
+        } else {
+            metadata.len()
+        };
+    }
+    cache
+        .lock()
+        .unwrap()
+        .insert(path.to_path_buf(), (mtime, Some(total)));
+    Some(total)
+}
+
+pub struct UsageEntry {
+    pub path: PathBuf,
+    pub size: Option<u64>,
+    pub is_dir: bool,
+}
+
+#[derive(Default)]
+pub struct DiskUsage {
+    pub root: PathBuf,
+    pub entries: Vec<UsageEntry>,
+    pub state: ListState,
+}
+
+impl DiskUsage {
+    pub fn open(&mut self, root: &Path) {
+        self.root = root.to_path_buf();
+        self.state.select_first();
+        self.reload();
+    }
+
+    /// Re-reads the listing of `root`, sizes are filled in by [`DiskUsage::update`].
+    pub fn reload(&mut self) {
+        self.entries = fs::read_dir(&self.root)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter_map(|entry| {
+                let metadata = entry.metadata().ok()?;
+                Some(UsageEntry {
+                    path: entry.path(),
+                    size: (!metadata.is_dir()).then_some(metadata.len()),
+                    is_dir: metadata.is_dir(),
+                })
+            })
+            .collect();
+    }
+
+    pub fn update(&mut self, sizes: &DirSizes) {
+        let selected = self.selected().map(|entry| entry.path.to_path_buf());
+        for entry in self.entries.iter_mut().filter(|entry| entry.is_dir) {
+            entry.size = sizes.get(&entry.path);
+        }
+        self.entries
+            .sort_by_key(|entry| (Reverse(entry.size), entry.path.to_path_buf()));
+
+        let index = selected
+            .and_then(|selected| self.entries.iter().position(|entry| entry.path == selected))
+            .or(self.state.selected())
+            .map(|index| index.min(self.entries.len().saturating_sub(1)));
+        self.state
+            .select(index.filter(|_| !self.entries.is_empty()));
+    }
+
+    pub fn selected(&self) -> Option<&UsageEntry> {
+        self.entries.get(self.state.selected()?)
+    }
+
+    pub fn total(&self) -> u64 {
+        self.entries.iter().filter_map(|entry| entry.size).sum()
+    }
+}
@@ -0,0 +1,397 @@
+#[cfg(unix)]
+use std::os::unix::fs::MetadataExt;
+use std::{
+    collections::{HashMap, HashSet},
+    fs::{self, File},
+    io::{Error, ErrorKind, Read, Result},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+};
+
+use ignore::WalkBuilder;
+use ratatui::widgets::ListState;
+use tokio::sync::mpsc::Sender;
+use xxhash_rust::xxh3::{xxh3_128, Xxh3};
+
+use crate::{archive, explorer::file_stamp};
+
+const PARTIAL_LEN: usize = 16 * 1024;
+const CHUNK: usize = 256 * 1024;
+
+type Candidates = Vec<(u64, Vec<PathBuf>)>;
+
+pub struct DuplicateGroup {
+    pub size: u64,
+    pub files: Vec<PathBuf>,
+}
+
+impl DuplicateGroup {
+    pub fn wasted(&self) -> u64 {
+        self.size * (self.files.len() as u64 - 1)
+    }
+}
+
+#[derive(Default)]
+pub struct DuplicateResults {
+    pub groups: Vec<DuplicateGroup>,
+    pub running: bool,
+    pub phase: &'static str,
+    pub done: usize,
+    pub total: usize,
+}
+
+#[derive(Default)]
+pub struct DuplicateFinder {
+    pub root: PathBuf,
+    pub results: Arc<Mutex<DuplicateResults>>,
+    pub state: ListState,
+    pub marked: HashSet<PathBuf>,
+    cancel: Arc<AtomicBool>,
+}
+
+impl DuplicateFinder {
+    pub fn start(&mut self, root: &Path, tx: Sender<String>) {
+        self.cancel.store(true, Ordering::Relaxed);
+        self.cancel = Arc::new(AtomicBool::new(false));
+        self.results = Arc::new(Mutex::new(DuplicateResults {
+            running: true,
+            phase: "scanning",
+            ..Default::default()
+        }));
+        self.root = root.to_path_buf();
+        self.state = ListState::default().with_selected(Some(0));
+        self.marked.clear();
+
+        let root = root.to_path_buf();
+        let results = Arc::clone(&self.results);
+        let cancel = Arc::clone(&self.cancel);
+        thread::spawn(move || {
+            let _ = archive::materialize(&[root.to_path_buf()]);
+            let groups = scan(&root, &results, &cancel);
+            let mut results = results.lock().unwrap();
+            if let Some(groups) = groups {
+                let mut groups: Vec<DuplicateGroup> = groups
+                    .into_iter()
+                    .map(|(size, mut files)| {
+                        files.sort();
+                        DuplicateGroup { size, files }
+                    })
+                    .collect();
+                groups.sort_by(|a, b| b.wasted().cmp(&a.wasted()).then(a.files.cmp(&b.files)));
+                results.groups = groups;
+            }
+            results.running = false;
+            let _ = tx.try_send(String::from("Duplicate scan completed"));
+        });
+    }
+
+    pub fn cancel(&self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.results.lock().unwrap().running
+    }
+
+    pub fn rows(&self) -> Vec<(usize, Option<usize>)> {
+        let results = self.results.lock().unwrap();
+        results
+            .groups
+            .iter()
+            .enumerate()
+            .flat_map(|(group, duplicates)| {
+                std::iter::once((group, None))
+                    .chain((0..duplicates.files.len()).map(move |file| (group, Some(file))))
+            })
+            .collect()
+    }
+
+    pub fn selected_file(&self) -> Option<PathBuf> {
+        let (group, file) = *self.rows().get(self.state.selected()?)?;
+        let results = self.results.lock().unwrap();
+        Some(results.groups[group].files[file?].to_path_buf())
+    }
+
+    pub fn toggle_mark(&mut self) {
+        if let Some(path) = self.selected_file() {
+            if !self.marked.remove(&path) {
+                self.marked.insert(path);
+            }
+        }
+    }
+
+    pub fn mark_extras(&mut self) {
+        let results = self.results.lock().unwrap();
+        for group in &results.groups {
+            self.marked.extend(group.files.iter().skip(1).cloned());
+        }
+    }
+
+    fn marked_groups(&self) -> Result<Vec<(PathBuf, Vec<PathBuf>)>> {
+        let results = self.results.lock().unwrap();
+        let mut marked_groups = Vec::new();
+        for group in &results.groups {
+            let (extras, kept): (Vec<PathBuf>, Vec<PathBuf>) = group
+                .files
+                .iter()
+                .cloned()
+                .partition(|path| self.marked.contains(path));
+            if extras.is_empty() {
+                continue;
+            }
+            let Some(keep) = kept.into_iter().next() else {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    "keep at least one file unmarked in every group",
+                ));
+            };
+            marked_groups.push((keep, extras));
+        }
+        Ok(marked_groups)
+    }
+
+    pub fn trash_marked(&mut self) -> Result<()> {
+        let mut trashed = Vec::new();
+        let mut done = Vec::new();
+        let mut changed = Vec::new();
+        for (keep, extras) in self.marked_groups()? {
+            for extra in extras {
+                match still_duplicate(&keep, &extra) {
+                    Ok(true) => trashed.push(extra),
+                    Ok(false) => done.push(extra),
+                    Err(_) => changed.push(extra),
+                }
+            }
+        }
+        trash::delete_all(&trashed).map_err(Error::other)?;
+        done.extend(trashed);
+        self.forget(&done);
+        skipped(&changed)
+    }
+
+    pub fn link_marked(&mut self) -> Result<()> {
+        let mut done = Vec::new();
+        let mut changed = Vec::new();
+        for (keep, extras) in self.marked_groups()? {
+            for extra in extras {
+                match still_duplicate(&keep, &extra) {
+                    Ok(true) => {}
+                    Ok(false) => {
+                        done.push(extra);
+                        continue;
+                    }
+                    Err(_) => {
+                        changed.push(extra);
+                        continue;
+                    }
+                }
+                if let Err(error) = replace_with_link(&keep, &extra) {
+                    self.forget(&done);
+                    return Err(error);
+                }
+                done.push(extra);
+            }
+        }
+        self.forget(&done);
+        skipped(&changed)
+    }
+
+    fn forget(&mut self, paths: &[PathBuf]) {
+        let mut results = self.results.lock().unwrap();
+        for group in results.groups.iter_mut() {
+            group.files.retain(|path| !paths.contains(path));
+        }
+        results.groups.retain(|group| group.files.len() > 1);
+        for path in paths {
+            self.marked.remove(path);
+        }
+        drop(results);
+
+        let rows = self.rows().len();
+        self.state.select(match self.state.selected() {
+            _ if rows == 0 => None,
+            Some(index) => Some(index.min(rows - 1)),
+            None => Some(0),
+        });
+    }
+}
+
+/// Re-checks right before acting that `extra` still holds the same bytes as `keep`, the
+/// files may have changed since the scan. `Ok(false)` means they're already one inode.
+fn still_duplicate(keep: &Path, extra: &Path) -> Result<bool> {
+    let before = (file_stamp(keep), file_stamp(extra));
+    #[cfg(unix)]
+    {
+        let (keep, extra) = (fs::metadata(keep)?, fs::metadata(extra)?);
+        if (keep.dev(), keep.ino()) == (extra.dev(), extra.ino()) {
+            return Ok(false);
+        }
+    }
+    let changed = || Error::new(ErrorKind::InvalidData, "file changed since the scan");
+    match before {
+        (Some(keep), Some(extra)) if keep.1 == extra.1 => {}
+        _ => return Err(changed()),
+    }
+    if !same_content(keep, extra)? || (file_stamp(keep), file_stamp(extra)) != before {
+        return Err(changed());
+    }
+    Ok(true)
+}
+
+fn same_content(first: &Path, second: &Path) -> Result<bool> {
+    let (mut first, mut second) = (File::open(first)?, File::open(second)?);
+    let (mut first_buf, mut second_buf) = (vec![0; CHUNK], vec![0; CHUNK]);
+    loop {
+        let read = read_chunk(&mut first, &mut first_buf)?;
+        if read != read_chunk(&mut second, &mut second_buf)?
+            || first_buf[..read] != second_buf[..read]
+        {
+            return Ok(false);
+        }
+        if read == 0 {
+            return Ok(true);
+        }
+    }
+}
+
+fn read_chunk(file: &mut File, buf: &mut [u8]) -> Result<usize> {
+    let mut total = 0;
+    while total < buf.len() {
+        match file.read(&mut buf[total..])? {
+            0 => break,
+            read => total += read,
+        }
+    }
+    Ok(total)
+}
+
+fn skipped(changed: &[PathBuf]) -> Result<()> {
+    match changed {
+        [] => Ok(()),
+        [path] => Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("skipped {}, it changed since the scan", path.display()),
+        )),
+        paths => Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("skipped {} files that changed since the scan", paths.len()),
+        )),
+    }
+}
+
+fn replace_with_link(keep: &Path, extra: &Path) -> Result<()> {
+    let name = extra
+        .file_name()
+        .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "invalid file name"))?;
+    let temporary = extra.with_file_name(format!(".{}.link", name.to_string_lossy()));
+    fs::hard_link(keep, &temporary)?;
+    fs::rename(&temporary, extra).inspect_err(|_| {
+        let _ = fs::remove_file(&temporary);
+    })
+}
+
+fn report(results: &Mutex<DuplicateResults>, phase: &'static str, done: usize, total: usize) {
+    let mut results = results.lock().unwrap();
+    results.phase = phase;
+    results.done = done;
+    results.total = total;
+}
+
+fn scan(root: &Path, results: &Mutex<DuplicateResults>, cancel: &AtomicBool) -> Option<Candidates> {
+    let mut by_size: HashMap<u64, Vec<PathBuf>> = HashMap::new();
+    #[cfg(unix)]
+    let mut inodes = HashSet::new();
+    let mut scanned = 0;
+    for entry in WalkBuilder::new(root)
+        .standard_filters(false)
+        .build()
+        .flatten()
+    {
+        if cancel.load(Ordering::Relaxed) {
+            return None;
+        }
+        if !entry.file_type().is_some_and(|kind| kind.is_file()) {
+            continue;
+        }
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        if metadata.len() == 0 {
+            continue;
+        }
+        #[cfg(unix)]
+        if !inodes.insert((metadata.dev(), metadata.ino())) {
+            continue;
+        }
+        by_size
+            .entry(metadata.len())
+            .or_default()
+            .push(entry.into_path());
+        scanned += 1;
+        report(results, "scanning", scanned, 0);
+    }
+
+    let candidates = by_size
+        .into_iter()
+        .filter(|(_, paths)| paths.len() > 1)
+        .collect();
+    let candidates = refine(candidates, partial_hash, "comparing", results, cancel)?;
+    refine(candidates, full_hash, "hashing", results, cancel)
+}
+
+fn refine(
+    candidates: Candidates,
+    hash: fn(&Path) -> Result<u128>,
+    phase: &'static str,
+    results: &Mutex<DuplicateResults>,
+    cancel: &AtomicBool,
+) -> Option<Candidates> {
+    let total = candidates.iter().map(|(_, paths)| paths.len()).sum();
+    let mut done = 0;
+    let mut refined = Vec::new();
+    for (size, paths) in candidates {
+        let mut by_hash: HashMap<u128, Vec<PathBuf>> = HashMap::new();
+        for path in paths {
+            if cancel.load(Ordering::Relaxed) {
+                return None;
+            }
+            if let Ok(hash) = hash(&path) {
+                by_hash.entry(hash).or_default().push(path);
+            }
+            done += 1;
+            report(results, phase, done, total);
+        }
+        refined.extend(
+            by_hash
+                .into_values()
+                .filter(|paths| paths.len() > 1)
+                .map(|paths| (size, paths)),
+        );
+    }
+    Some(refined)
+}
+
+fn partial_hash(path: &Path) -> Result<u128> {
+    let mut buf = Vec::with_capacity(PARTIAL_LEN);
+    File::open(path)?
+        .take(PARTIAL_LEN as u64)
+        .read_to_end(&mut buf)?;
+    Ok(xxh3_128(&buf))
+}
+
+fn full_hash(path: &Path) -> Result<u128> {
+    let mut file = File::open(path)?;
+    let mut hasher = Xxh3::new();
+    let mut buf = vec![0; CHUNK];
+    loop {
+        match file.read(&mut buf)? {
+            0 => break,
+            read => hasher.update(&buf[..read]),
+        }
+    }
+    Ok(hasher.digest128())
+}
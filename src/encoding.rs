@@ -0,0 +1,251 @@
+use std::{
+    fmt,
+    io::{Error, ErrorKind, Result},
+};
+
+use chardetng::{EncodingDetector, Iso2022JpDetection, Utf8Detection};
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8};
+
+use crate::explorer::looks_binary;
+
+pub const SNIFF_LEN: usize = 64 * 1024;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum LineEnding {
+    Lf,
+    Crlf,
+}
+
+impl LineEnding {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::Crlf => "\r\n",
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct TextFormat {
+    pub encoding: &'static Encoding,
+    pub bom: bool,
+    pub line_ending: LineEnding,
+    /// Some bytes were invalid in `encoding` and decoded to U+FFFD.
+    pub lossy: bool,
+}
+
+impl Default for TextFormat {
+    fn default() -> Self {
+        Self {
+            encoding: UTF_8,
+            bom: false,
+            line_ending: LineEnding::Lf,
+            lossy: false,
+        }
+    }
+}
+
+impl fmt::Display for TextFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let line_ending = match self.line_ending {
+            LineEnding::Lf => "LF",
+            LineEnding::Crlf => "CRLF",
+        };
+        let bom = if self.bom { " BOM" } else { "" };
+        write!(f, "{}{} {}", self.encoding.name(), bom, line_ending)
+    }
+}
+
+impl TextFormat {
+    pub fn detect(bytes: &[u8]) -> Option<&'static Encoding> {
+        if let Some((encoding, _)) = Encoding::for_bom(bytes) {
+            return Some(encoding);
+        }
+        if let Some(encoding) = utf16_without_bom(bytes) {
+            return Some(encoding);
+        }
+        if looks_binary(bytes) {
+            return None;
+        }
+        let sniff = &bytes[..bytes.len().min(SNIFF_LEN)];
+        if is_utf8(sniff) && (sniff.len() == bytes.len() || is_utf8(bytes)) {
+            return Some(UTF_8);
+        }
+        let mut detector = EncodingDetector::new(Iso2022JpDetection::Deny);
+        detector.feed(sniff, sniff.len() == bytes.len());
+        Some(detector.guess(None, Utf8Detection::Allow))
+    }
+
+    pub fn decode(bytes: &[u8]) -> Option<(String, TextFormat)> {
+        let (encoding, bom_len) = match Encoding::for_bom(bytes) {
+            Some((encoding, bom_len)) => (encoding, bom_len),
+            None => (TextFormat::detect(bytes)?, 0),
+        };
+        let (text, lossy) = encoding.decode_without_bom_handling(&bytes[bom_len..]);
+        let format = TextFormat {
+            encoding,
+            bom: bom_len > 0,
+            line_ending: detect_line_ending(&text),
+            lossy,
+        };
+        Some((text.into_owned(), format))
+    }
+
+    pub fn encode(&self, text: &str) -> Result<Vec<u8>> {
+        if self.lossy {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "the file has bytes that aren't valid {}, saving would replace them",
+                    self.encoding.name()
+                ),
+            ));
+        }
+        let mut bytes = Vec::with_capacity(text.len() + 3);
+        if self.encoding == UTF_16LE || self.encoding == UTF_16BE {
+            let little_endian = self.encoding == UTF_16LE;
+            let units = self
+                .bom
+                .then_some('\u{feff}' as u16)
+                .into_iter()
+                .chain(text.encode_utf16());
+            for unit in units {
+                if little_endian {
+                    bytes.extend_from_slice(&unit.to_le_bytes());
+                } else {
+                    bytes.extend_from_slice(&unit.to_be_bytes());
+                }
+            }
+            return Ok(bytes);
+        }
+
+        if self.bom && self.encoding == UTF_8 {
+            bytes.extend_from_slice(b"\xEF\xBB\xBF");
+        }
+        let (encoded, _, had_errors) = self.encoding.encode(text);
+        if had_errors {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "the text has characters that can't be saved as {}",
+                    self.encoding.name()
+                ),
+            ));
+        }
+        bytes.extend_from_slice(&encoded);
+        Ok(bytes)
+    }
+}
+
+/// Valid UTF-8, allowing a sequence cut off at the end of `bytes`.
+fn is_utf8(bytes: &[u8]) -> bool {
+    match std::str::from_utf8(bytes) {
+        Ok(_) => true,
+        Err(error) => error.error_len().is_none(),
+    }
+}
+
+fn utf16_without_bom(bytes: &[u8]) -> Option<&'static Encoding> {
+    let sniff = &bytes[..bytes.len().min(SNIFF_LEN) & !1];
+    if sniff.len() < 4 {
+        return None;
+    }
+    let pairs = sniff.len() / 2;
+    let even_zeros = sniff.iter().step_by(2).filter(|&&byte| byte == 0).count();
+    let odd_zeros = sniff
+        .iter()
+        .skip(1)
+        .step_by(2)
+        .filter(|&&byte| byte == 0)
+        .count();
+    if odd_zeros * 10 > pairs * 4 && even_zeros * 10 < pairs {
+        Some(UTF_16LE)
+    } else if even_zeros * 10 > pairs * 4 && odd_zeros * 10 < pairs {
+        Some(UTF_16BE)
+    } else {
+        None
+    }
+}
+
+fn detect_line_ending(text: &str) -> LineEnding {
+    let crlf = text.matches("\r\n").count();
+    let lf = text.matches('\n').count() - crlf;
+    if crlf > 0 && crlf >= lf {
+        LineEnding::Crlf
+    } else {
+        LineEnding::Lf
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use encoding_rs::WINDOWS_1252;
+
+    use super::*;
+
+    fn utf16(text: &str, little_endian: bool, bom: bool) -> Vec<u8> {
+        let units = bom.then_some(0xfeff).into_iter().chain(text.encode_utf16());
+        units
+            .flat_map(|unit: u16| match little_endian {
+                true => unit.to_le_bytes(),
+                false => unit.to_be_bytes(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn utf8_bom_round_trips() {
+        let bytes = b"\xEF\xBB\xBFhello\n";
+        let (text, format) = TextFormat::decode(bytes).unwrap();
+        assert_eq!(text, "hello\n");
+        assert!(format.encoding == UTF_8 && format.bom);
+        assert_eq!(format.encode(&text).unwrap(), bytes);
+    }
+
+    #[test]
+    fn utf16_with_and_without_bom() {
+        let cases = [
+            (true, true, UTF_16LE),
+            (true, false, UTF_16LE),
+            (false, true, UTF_16BE),
+            (false, false, UTF_16BE),
+        ];
+        for (little_endian, bom, encoding) in cases {
+            let bytes = utf16("line one\nline two\n", little_endian, bom);
+            let (text, format) = TextFormat::decode(&bytes).unwrap();
+            assert_eq!(text, "line one\nline two\n");
+            assert!(format.encoding == encoding, "{}", format);
+            assert_eq!(format.bom, bom);
+            assert_eq!(format.encode(&text).unwrap(), bytes);
+        }
+    }
+
+    #[test]
+    fn crlf_is_preserved() {
+        let (text, format) = TextFormat::decode(b"a\r\nb\r\n").unwrap();
+        assert!(format.line_ending == LineEnding::Crlf);
+        assert_eq!(format.encode(&text).unwrap(), b"a\r\nb\r\n");
+
+        let (_, format) = TextFormat::decode(b"a\nb\n").unwrap();
+        assert!(format.line_ending == LineEnding::Lf);
+    }
+
+    #[test]
+    fn encode_refuses_lossy_text() {
+        let format = TextFormat {
+            lossy: true,
+            ..Default::default()
+        };
+        assert!(format.encode("text").is_err());
+    }
+
+    #[test]
+    fn encode_refuses_unencodable_text() {
+        let format = TextFormat {
+            encoding: WINDOWS_1252,
+            ..Default::default()
+        };
+        assert_eq!(format.encode("café").unwrap(), b"caf\xE9");
+        assert!(format.encode("日本").is_err());
+    }
+}
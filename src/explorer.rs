@@ -2,11 +2,58 @@ use ratatui::widgets::ListState;
 #[cfg(unix)]
 use std::os::unix::fs::PermissionsExt;
 use std::{
+    collections::HashSet,
     fs::{self, File},
-    io::{Error, ErrorKind, Result},
+    io::{Error, ErrorKind, Result, Write},
+    ops::Range,
     path::{Path, PathBuf},
+    time::SystemTime,
 };
 
+use crate::{
+    archive,
+    encoding::TextFormat,
+    history::History,
+    image_preview::is_image,
+    preview::{LargeFile, LARGE_FILE},
+};
+
+pub const SNIFF_LEN: usize = 8192;
+pub const HEX_WIDTH: usize = 16;
+
+pub fn looks_binary(bytes: &[u8]) -> bool {
+    bytes[..bytes.len().min(SNIFF_LEN)].contains(&0)
+}
+
+pub type FileStamp = (SystemTime, u64);
+
+pub fn file_stamp(path: &Path) -> Option<FileStamp> {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+/// Byte ranges of the lines `str::lines` would yield, without their line breaks.
+fn line_ranges(text: &str) -> Vec<Range<usize>> {
+    let mut start = 0;
+    text.split_inclusive('\n')
+        .map(|piece| {
+            let line = match piece.strip_suffix('\n') {
+                Some(line) => line.strip_suffix('\r').unwrap_or(line),
+                None => piece,
+            };
+            let range = start..start + line.len();
+            start += piece.len();
+            range
+        })
+        .collect()
+}
+
+fn write_synced(path: &Path, bytes: &[u8]) -> Result<()> {
+    let mut file = File::options().write(true).create_new(true).open(path)?;
+    file.write_all(bytes)?;
+    file.sync_all()
+}
+
 #[derive(Default)]
 pub struct FileStruct {
     pub pwd: PathBuf,
@@ -20,8 +67,18 @@ pub struct FileStruct {
     pub parent_state: ListState,
     pub next_dir: Vec<PathBuf>,
     pub error: Option<Error>,
+    /// Shown in place of `error` when there is none, until the next key press.
+    pub status: Option<String>,
     pub content: String,
+    /// Byte ranges of the lines in `content`, so lookups don't rescan it.
+    pub lines: Vec<Range<usize>>,
+    pub content_stamp: Option<(PathBuf, SystemTime)>,
+    pub binary: Option<Vec<u8>>,
+    pub large: Option<LargeFile>,
+    pub text_format: Option<TextFormat>,
     pub permission: String,
+    pub history: History,
+    pub marked: HashSet<PathBuf>,
 }
 
 trait FileFun {
@@ -52,7 +109,7 @@ impl FileFun for FileStruct {
 
     fn parent_dir_fn(&mut self) {
         let mut files: Vec<PathBuf> = vec![];
-        if let Some(parent) = self.pwd.parent() {
+        if let Some(parent) = archive::entry_of(&self.pwd).parent() {
             files = FileStruct::get_dirs_and_files(parent);
         }
         self.parent_dir = files;
@@ -82,17 +139,49 @@ impl FileFun for FileStruct {
 
 impl FileStruct {
     pub fn present_dir_fn(&mut self, path: &Path, index: Option<usize>) {
-        let pwd = fs::canonicalize(path).unwrap();
+        let pwd = match fs::canonicalize(path) {
+            Ok(pwd) => pwd,
+            Err(error) => {
+                self.error = Some(error);
+                return;
+            }
+        };
+        if !self.pwd.as_os_str().is_empty() && self.pwd != pwd {
+            let previous = self.pwd.to_path_buf();
+            self.history.visit(previous, self.current_state.selected());
+        }
+        self.load_dir(pwd, index);
+    }
+
+    pub fn go_back(&mut self) {
+        let current = self.pwd.to_path_buf();
+        if let Some((path, index)) = self.history.back(current, self.current_state.selected()) {
+            self.load_dir(path, index);
+        }
+    }
+
+    pub fn go_forward(&mut self) {
+        let current = self.pwd.to_path_buf();
+        if let Some((path, index)) = self.history.forward(current, self.current_state.selected()) {
+            self.load_dir(path, index);
+        }
+    }
+
+    fn load_dir(&mut self, pwd: PathBuf, index: Option<usize>) {
         self.error = None;
+        if self.pwd != pwd {
+            self.marked.clear();
+        }
         self.pwd = pwd.to_path_buf();
-        if let Some(parent) = self.pwd.parent() {
+        if let Some(parent) = archive::entry_of(&self.pwd).parent() {
             self.parent = parent.to_path_buf()
         }
         let files = FileStruct::get_dirs_and_files(pwd.as_path());
 
-        let index = index.unwrap_or_default();
-        self.current_state.select(Some(index));
-        self.current_path = Some(files[index].to_path_buf());
+        let index = index.unwrap_or_default().min(files.len().saturating_sub(1));
+        self.current_state
+            .select((!files.is_empty()).then_some(index));
+        self.current_path = files.get(index).map(|path| path.to_path_buf());
         if !files.is_empty() {
             if files[index].is_dir() {
                 self.next_dir_fn(files[index].as_path());
@@ -109,6 +198,34 @@ impl FileStruct {
         self.parent_dir_fn();
     }
 
+    pub fn refresh(&mut self) {
+        let mut pwd = self.pwd.to_path_buf();
+        while !pwd.is_dir() {
+            if !pwd.pop() {
+                return;
+            }
+        }
+        let files = FileStruct::get_dirs_and_files(&pwd);
+        let index = self
+            .current_path
+            .as_ref()
+            .and_then(|selected| files.iter().position(|path| path == selected))
+            .or(self.current_state.selected());
+        // A background refresh shouldn't wipe an error the user hasn't seen yet.
+        let error = self.error.take();
+        self.present_dir_fn(&pwd, index);
+        self.error = self.error.take().or(error);
+    }
+
+    pub fn reveal(&mut self, path: &Path) {
+        if let Some(parent) = path.parent() {
+            let index = FileStruct::get_dirs_and_files(parent)
+                .iter()
+                .position(|entry| entry == path);
+            self.present_dir_fn(parent, index);
+        }
+    }
+
     pub fn next_dir_fn(&mut self, path: &Path) {
         #[cfg(unix)]
         self.file_permission(path);
@@ -118,16 +235,101 @@ impl FileStruct {
     }
 
     pub fn read_file(&mut self, path: PathBuf) {
+        if let Err(error) = archive::materialize(&[path.to_path_buf()]) {
+            self.error = Some(error);
+        }
         #[cfg(unix)]
         self.file_permission(path.as_path());
-        let line = fs::read_to_string(path).unwrap_or_else(|error| {
-            self.error = Some(error);
-            String::new()
-        });
-        self.line_count = line.lines().count();
+        self.content_stamp = fs::metadata(&path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .map(|modified| (path.to_path_buf(), modified));
+        if self.large.as_ref().is_some_and(|large| large.path == path) {
+            return;
+        }
+        self.binary = None;
+        self.large = None;
+        self.text_format = None;
+        if is_image(&path) {
+            self.content.clear();
+            self.lines.clear();
+            self.line_count = 0;
+            return;
+        }
+        if fs::metadata(&path).is_ok_and(|metadata| metadata.len() > LARGE_FILE) {
+            self.content.clear();
+            self.lines.clear();
+            match LargeFile::open(&path) {
+                Ok(large) => {
+                    self.text_format = large.format;
+                    self.large = Some(large)
+                }
+                Err(error) => self.error = Some(error),
+            }
+            return;
+        }
+        let line = match fs::read(path) {
+            Ok(bytes) => match TextFormat::decode(&bytes) {
+                Some((text, format)) => {
+                    self.text_format = Some(format);
+                    text
+                }
+                None => {
+                    self.binary = Some(bytes);
+                    String::new()
+                }
+            },
+            Err(error) => {
+                self.error = Some(error);
+                String::new()
+            }
+        };
+        self.lines = match &self.binary {
+            Some(_) => Vec::new(),
+            None => line_ranges(&line),
+        };
+        self.line_count = match &self.binary {
+            Some(bytes) => bytes.len().div_ceil(HEX_WIDTH),
+            None => self.lines.len(),
+        };
         self.content = line;
     }
 
+    pub fn content_line(&self, index: usize) -> Option<&str> {
+        self.content.get(self.lines.get(index)?.clone())
+    }
+
+    pub fn total_lines(&self) -> usize {
+        match &self.large {
+            Some(large) => large.line_count(),
+            None => self.line_count,
+        }
+    }
+
+    pub fn toggle_follow(&mut self) {
+        if self.large.is_none() {
+            let Some(path) = self.current_path.clone().filter(|path| path.is_file()) else {
+                return;
+            };
+            match LargeFile::open(&path) {
+                Ok(large) => {
+                    self.content.clear();
+                    self.lines.clear();
+                    self.binary = None;
+                    self.text_format = large.format;
+                    self.large = Some(large);
+                }
+                Err(error) => {
+                    self.error = Some(error);
+                    return;
+                }
+            }
+        }
+        if let Some(large) = self.large.as_mut() {
+            large.follow = !large.follow;
+        }
+    }
+
     #[cfg(unix)]
     fn file_permission(&mut self, path: &Path) {
         match fs::metadata(path) {
@@ -169,6 +371,94 @@ impl FileStruct {
         }
     }
 
+    pub fn dir_name(&self) -> String {
+        self.pwd
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| self.pwd.to_string_lossy().to_string())
+    }
+
+    pub fn selection(&self) -> Vec<PathBuf> {
+        if self.marked.is_empty() {
+            return self.current_path.iter().cloned().collect();
+        }
+        self.current_dir
+            .iter()
+            .filter(|path| self.marked.contains(*path))
+            .cloned()
+            .collect()
+    }
+
+    pub fn toggle_mark(&mut self) {
+        if let Some(path) = self.current_path.clone() {
+            if !self.marked.remove(&path) {
+                self.marked.insert(path);
+            }
+        }
+    }
+
+    pub fn in_archive(&self) -> bool {
+        archive::mount_of(&self.pwd).is_some()
+    }
+
+    pub fn copy_to(path: &Path, dest_dir: &Path) -> Result<()> {
+        let target = FileStruct::target_path(path, dest_dir)?;
+        FileStruct::copy_recursive(path, &target)
+    }
+
+    pub fn move_to(path: &Path, dest_dir: &Path) -> Result<()> {
+        let target = FileStruct::target_path(path, dest_dir)?;
+        if fs::rename(path, &target).is_err() {
+            FileStruct::copy_recursive(path, &target)?;
+            if path.is_dir() {
+                fs::remove_dir_all(path)?;
+            } else {
+                fs::remove_file(path)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn target_path(path: &Path, dest_dir: &Path) -> Result<PathBuf> {
+        let name = path
+            .file_name()
+            .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "invalid file name"))?;
+        let target = dest_dir.join(name);
+        if target.exists() {
+            return Err(Error::new(
+                ErrorKind::AlreadyExists,
+                format!("{} already exists", target.display()),
+            ));
+        }
+        if dest_dir.starts_with(path) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "can't copy a directory into itself",
+            ));
+        }
+        Ok(target)
+    }
+
+    fn copy_recursive(path: &Path, target: &Path) -> Result<()> {
+        let metadata = fs::symlink_metadata(path)?;
+        if metadata.is_dir() {
+            fs::create_dir(target)?;
+            for entry in fs::read_dir(path)? {
+                let entry = entry?;
+                FileStruct::copy_recursive(&entry.path(), &target.join(entry.file_name()))?;
+            }
+            fs::set_permissions(target, metadata.permissions())?;
+        } else if metadata.file_type().is_symlink() {
+            #[cfg(unix)]
+            std::os::unix::fs::symlink(fs::read_link(path)?, target)?;
+            #[cfg(not(unix))]
+            fs::copy(path, target).map(|_| ())?;
+        } else {
+            fs::copy(path, target)?;
+        }
+        Ok(())
+    }
+
     pub fn rename(&mut self, rename: &str) {
         if let Some(path) = &self.current_path {
             if let Some(re) = path.parent() {
@@ -184,16 +474,38 @@ impl FileStruct {
         Ok(())
     }
 
-    pub fn file_write(&mut self, content: String) {
-        if let Some(path) = &self.current_path {
-            fs::write(path, content).unwrap_or_else(|error| self.error = Some(error));
+    pub fn file_write(path: &Path, content: &str, format: &TextFormat) -> Result<()> {
+        let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        let name = path
+            .file_name()
+            .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "invalid file name"))?;
+        let temporary = path.with_file_name(format!(
+            ".{}.{}.save",
+            name.to_string_lossy(),
+            std::process::id()
+        ));
+        let permissions = fs::metadata(&path).map(|metadata| metadata.permissions());
+
+        write_synced(&temporary, &format.encode(content)?)
+            .and_then(|()| match permissions {
+                Ok(permissions) => fs::set_permissions(&temporary, permissions),
+                Err(_) => Ok(()),
+            })
+            .and_then(|()| fs::rename(&temporary, &path))
+            .inspect_err(|_| {
+                let _ = fs::remove_file(&temporary);
+            })?;
+        if let Some(parent) = path.parent() {
+            let _ = File::open(parent).and_then(|dir| dir.sync_all());
         }
+        Ok(())
     }
 
-    pub fn file_read(&mut self) -> Result<String> {
+    pub fn file_read(&mut self) -> Result<(String, TextFormat)> {
         if let Some(path) = &self.current_path {
-            let content = fs::read_to_string(path)?;
-            return Ok(content);
+            let bytes = fs::read(path)?;
+            return TextFormat::decode(&bytes)
+                .ok_or_else(|| Error::new(ErrorKind::InvalidData, "can't edit a binary file"));
         }
         Err(Error::new(ErrorKind::Unsupported, ""))
     }
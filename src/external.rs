@@ -0,0 +1,172 @@
+use std::{
+    env,
+    ffi::{OsStr, OsString},
+    io::{self, Error, ErrorKind, Result, Write},
+    path::{Path, PathBuf},
+    process::Stdio,
+};
+
+use tokio::process::Command;
+
+const PLACEHOLDER: &str = "{}";
+
+#[derive(Clone, Copy)]
+pub enum ExternalKind {
+    Editor,
+    Pager,
+}
+
+pub struct ExternalCommand {
+    program: String,
+    args: Vec<OsString>,
+    dir: Option<PathBuf>,
+    pause: bool,
+}
+
+impl ExternalCommand {
+    pub fn new(kind: ExternalKind, files: Vec<PathBuf>) -> Result<Self> {
+        let (variables, fallback) = match kind {
+            ExternalKind::Editor => (&["VISUAL", "EDITOR"][..], "vi"),
+            ExternalKind::Pager => (&["PAGER"][..], "less"),
+        };
+        let command = variables
+            .iter()
+            .filter_map(|variable| env::var(variable).ok())
+            .find(|value| !value.trim().is_empty())
+            .unwrap_or_else(|| fallback.to_string());
+        let words = split_words(&command).ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                format!("unbalanced quotes in {}", command),
+            )
+        })?;
+        Self::from_template(&words, &files)
+    }
+
+    pub fn from_template(words: &[String], files: &[PathBuf]) -> Result<Self> {
+        let (program, words) = words
+            .split_first()
+            .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "empty command"))?;
+        let mut args: Vec<OsString> = Vec::new();
+        for word in words {
+            if word == PLACEHOLDER {
+                args.extend(files.iter().map(|file| file.as_os_str().to_owned()));
+            } else {
+                args.push(word.into());
+            }
+        }
+        if !words.iter().any(|word| word == PLACEHOLDER) {
+            args.extend(files.iter().map(|file| file.as_os_str().to_owned()));
+        }
+        Ok(Self {
+            program: program.to_string(),
+            args,
+            dir: None,
+            pause: false,
+        })
+    }
+
+    /// Runs `script` with `sh -c` in `dir`, waiting for Enter before returning.
+    pub fn shell(script: &OsStr, dir: &Path) -> Self {
+        Self {
+            program: String::from("sh"),
+            args: vec!["-c".into(), script.into()],
+            dir: Some(dir.to_path_buf()),
+            pause: true,
+        }
+    }
+
+    fn command(&self) -> Command {
+        let mut command = Command::new(&self.program);
+        command.args(&self.args);
+        if let Some(dir) = &self.dir {
+            command.current_dir(dir);
+        }
+        command
+    }
+
+    fn describe(&self, error: Error) -> Error {
+        Error::new(error.kind(), format!("{}: {}", self.program, error))
+    }
+
+    /// Runs in the foreground and describes how the program exited.
+    pub async fn run(&self) -> Result<String> {
+        let status = self
+            .command()
+            .status()
+            .await
+            .map_err(|error| self.describe(error))?;
+        if self.pause {
+            print!("\nPress Enter to continue");
+            io::stdout().flush()?;
+            tokio::task::spawn_blocking(|| io::stdin().read_line(&mut String::new()))
+                .await
+                .map_err(Error::other)??;
+        }
+        if !status.success() {
+            return Err(Error::other(format!(
+                "{} exited with {}",
+                self.program, status
+            )));
+        }
+        Ok(format!("{} exited with {}", self.program, status))
+    }
+
+    pub fn spawn_detached(&self) -> Result<()> {
+        let mut command = self.command();
+        command
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null());
+        #[cfg(unix)]
+        command.process_group(0);
+        command.spawn().map_err(|error| self.describe(error))?;
+        Ok(())
+    }
+}
+
+/// Splits on whitespace, keeping single or double quoted runs together.
+pub fn split_words(line: &str) -> Option<Vec<String>> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut quote = None;
+    for ch in line.chars() {
+        match (quote, ch) {
+            (Some(open), ch) if ch == open => quote = None,
+            (Some(_), ch) => word.get_or_insert_with(String::new).push(ch),
+            (None, '"' | '\'') => {
+                quote = Some(ch);
+                word.get_or_insert_with(String::new);
+            }
+            (None, ch) if ch.is_whitespace() => words.extend(word.take()),
+            (None, ch) => word.get_or_insert_with(String::new).push(ch),
+        }
+    }
+    if quote.is_some() {
+        return None;
+    }
+    words.extend(word);
+    Some(words)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_words_keeps_quoted_runs() {
+        let cases: [(&str, Option<&[&str]>); 7] = [
+            ("", Some(&[])),
+            ("  vi  -R ", Some(&["vi", "-R"])),
+            ("code --wait", Some(&["code", "--wait"])),
+            ("\"my editor\" -x", Some(&["my editor", "-x"])),
+            ("a'b c'd", Some(&["ab cd"])),
+            ("say '' done", Some(&["say", "", "done"])),
+            ("vim \"unbalanced", None),
+        ];
+        for (line, words) in cases {
+            let words = words.map(|words| words.iter().map(|word| word.to_string()).collect());
+            assert_eq!(split_words(line), words, "{:?}", line);
+        }
+    }
+}
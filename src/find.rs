@@ -0,0 +1,190 @@
+use std::{
+    io::{Error, ErrorKind, Result},
+    ops::Range,
+};
+
+use regex::{Regex, RegexBuilder};
+
+use crate::ui::ViewMode;
+
+pub type Match = (usize, Range<usize>);
+
+#[derive(Default)]
+pub struct Finder {
+    pub query: String,
+    pub replacement: String,
+    pub regex: bool,
+    pub ignore_case: bool,
+    pub invalid: bool,
+    pub current: Option<Match>,
+    pub reveal: bool,
+    pub origin: Option<(ViewMode, usize, usize)>,
+    matcher: Option<Regex>,
+}
+
+impl Finder {
+    pub fn compile(&mut self) -> Result<()> {
+        self.matcher = None;
+        self.invalid = false;
+        if self.query.is_empty() {
+            return Ok(());
+        }
+        let pattern = if self.regex {
+            self.query.to_string()
+        } else {
+            regex::escape(&self.query)
+        };
+        let matcher = RegexBuilder::new(&pattern)
+            .case_insensitive(self.ignore_case)
+            .build()
+            .map_err(|error| {
+                self.invalid = true;
+                Error::new(ErrorKind::InvalidInput, error)
+            })?;
+        self.matcher = Some(matcher);
+        Ok(())
+    }
+
+    pub fn clear(&mut self) {
+        self.query.clear();
+        self.matcher = None;
+        self.invalid = false;
+        self.current = None;
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.matcher.is_some()
+    }
+
+    pub fn matches(&self, line: &str) -> Vec<Range<usize>> {
+        let Some(matcher) = &self.matcher else {
+            return Vec::new();
+        };
+        matcher
+            .find_iter(line)
+            .map(|found| found.range())
+            .filter(|range| !range.is_empty())
+            .collect()
+    }
+
+    pub fn find(
+        &self,
+        count: usize,
+        line: impl Fn(usize) -> String,
+        from: (usize, usize),
+        forward: bool,
+        inclusive: bool,
+    ) -> Option<Match> {
+        if count == 0 || !self.is_active() {
+            return None;
+        }
+        let (start, offset) = (from.0.min(count - 1), from.1);
+        let ahead =
+            |range: &Range<usize>| range.start > offset || (inclusive && range.start == offset);
+
+        let matches = self.matches(&line(start));
+        let found = if forward {
+            matches.iter().find(|range| ahead(range))
+        } else {
+            matches.iter().rev().find(|range| range.start < offset)
+        };
+        if let Some(range) = found {
+            return Some((start, range.clone()));
+        }
+        for step in 1..=count {
+            let index = if forward {
+                (start + step) % count
+            } else {
+                (start + count - step % count) % count
+            };
+            let matches = self.matches(&line(index));
+            let found = match (forward, index == start) {
+                (true, true) => matches.into_iter().find(|range| !ahead(range)),
+                (true, false) => matches.into_iter().next(),
+                (false, true) => matches
+                    .into_iter()
+                    .rev()
+                    .find(|range| range.start >= offset),
+                (false, false) => matches.into_iter().next_back(),
+            };
+            if let Some(range) = found {
+                return Some((index, range));
+            }
+        }
+        None
+    }
+
+    pub fn expand(&self, line: &str, range: &Range<usize>, replacement: &str) -> Option<String> {
+        let captures = self.matcher.as_ref()?.captures_at(line, range.start)?;
+        if captures.get(0)?.range() != *range {
+            return None;
+        }
+        if !self.regex {
+            return Some(replacement.to_string());
+        }
+        let mut expanded = String::new();
+        captures.expand(replacement, &mut expanded);
+        Some(expanded)
+    }
+
+    /// Replaces what [`Finder::matches`] finds, so empty matches find never stops at are
+    /// left alone.
+    pub fn replace_all(&self, line: &str, replacement: &str) -> Option<String> {
+        let matches = self.matches(line);
+        if matches.is_empty() {
+            return None;
+        }
+        let mut replaced = String::with_capacity(line.len());
+        let mut last = 0;
+        for range in matches {
+            replaced.push_str(&line[last..range.start]);
+            match self.expand(line, &range, replacement) {
+                Some(expanded) => replaced.push_str(&expanded),
+                None => replaced.push_str(&line[range.clone()]),
+            }
+            last = range.end;
+        }
+        replaced.push_str(&line[last..]);
+        Some(replaced)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn finder(query: &str, regex: bool) -> Finder {
+        let mut finder = Finder {
+            query: query.to_string(),
+            regex,
+            ..Default::default()
+        };
+        finder.compile().unwrap();
+        finder
+    }
+
+    #[test]
+    fn replace_all_skips_empty_matches() {
+        let finder = finder("x*", true);
+        assert_eq!(finder.replace_all("abxxc", "-"), Some(String::from("ab-c")));
+        assert_eq!(finder.replace_all("abc", "-"), None);
+    }
+
+    #[test]
+    fn replace_all_expands_captures() {
+        let finder = finder(r"(\w+)=(\w+)", true);
+        assert_eq!(
+            finder.replace_all("a=1 b=2", "$2=$1"),
+            Some(String::from("1=a 2=b"))
+        );
+    }
+
+    #[test]
+    fn replace_all_keeps_literal_dollars() {
+        let finder = finder("a.b", false);
+        assert_eq!(
+            finder.replace_all("a.b axb a.b", "$1"),
+            Some(String::from("$1 axb $1"))
+        );
+    }
+}
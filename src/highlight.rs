@@ -0,0 +1,130 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, OnceLock},
+    thread,
+    time::SystemTime,
+};
+
+use ratatui::{
+    style::{Color, Style},
+    text::{Line, Span},
+};
+use syntect::{
+    easy::HighlightLines,
+    highlighting::Theme,
+    parsing::{SyntaxReference, SyntaxSet},
+    util::LinesWithEndings,
+};
+use tokio::sync::mpsc::Sender;
+use two_face::theme::EmbeddedThemeName;
+
+use crate::explorer::FileStruct;
+
+const MAX_HIGHLIGHT_BYTES: usize = 4 * 1024 * 1024;
+const MAX_CACHED_FILES: usize = 32;
+
+type Stamp = (PathBuf, SystemTime);
+type Highlighted = Arc<Vec<Line<'static>>>;
+type Cache = HashMap<PathBuf, (SystemTime, Option<Highlighted>)>;
+
+static SYNTAXES: OnceLock<SyntaxSet> = OnceLock::new();
+static THEME: OnceLock<Theme> = OnceLock::new();
+
+#[derive(Default)]
+pub struct Highlighter {
+    cache: Arc<Mutex<Cache>>,
+    pending: Arc<Mutex<HashSet<Stamp>>>,
+}
+
+impl Highlighter {
+    pub fn get(&self, stamp: &Stamp) -> Option<Highlighted> {
+        let cache = self.cache.lock().unwrap();
+        match cache.get(&stamp.0) {
+            Some((modified, lines)) if *modified == stamp.1 => lines.clone(),
+            _ => None,
+        }
+    }
+
+    fn is_cached(&self, stamp: &Stamp) -> bool {
+        let cache = self.cache.lock().unwrap();
+        cache
+            .get(&stamp.0)
+            .is_some_and(|(modified, _)| *modified == stamp.1)
+    }
+
+    pub fn sync(&mut self, files: &Arc<Mutex<FileStruct>>, tx: &Sender<String>) {
+        let (stamp, content) = {
+            let file_struct = files.lock().unwrap();
+            let Some(stamp) = file_struct.content_stamp.clone() else {
+                return;
+            };
+            if file_struct.content.is_empty()
+                || file_struct.content.len() > MAX_HIGHLIGHT_BYTES
+                || self.is_cached(&stamp)
+            {
+                return;
+            }
+            if !self.pending.lock().unwrap().insert(stamp.clone()) {
+                return;
+            }
+            (stamp, file_struct.content.to_string())
+        };
+
+        let cache = Arc::clone(&self.cache);
+        let pending = Arc::clone(&self.pending);
+        let tx = tx.clone();
+        thread::spawn(move || {
+            let lines = highlight(&stamp.0, &content);
+            {
+                let mut cache = cache.lock().unwrap();
+                if cache.len() >= MAX_CACHED_FILES {
+                    cache.clear();
+                }
+                cache.insert(stamp.0.to_path_buf(), (stamp.1, lines.map(Arc::new)));
+            }
+            pending.lock().unwrap().remove(&stamp);
+            let _ = tx.try_send(String::new());
+        });
+    }
+}
+
+fn find_syntax<'a>(
+    syntaxes: &'a SyntaxSet,
+    path: &Path,
+    content: &str,
+) -> Option<&'a SyntaxReference> {
+    syntaxes
+        .find_syntax_for_file(path)
+        .ok()
+        .flatten()
+        .or_else(|| syntaxes.find_syntax_by_first_line(content.lines().next()?))
+}
+
+fn highlight(path: &Path, content: &str) -> Option<Vec<Line<'static>>> {
+    let syntaxes = SYNTAXES.get_or_init(two_face::syntax::extra_newlines);
+    let theme = THEME.get_or_init(|| {
+        two_face::theme::extra()
+            .get(EmbeddedThemeName::MonokaiExtended)
+            .clone()
+    });
+    let syntax = find_syntax(syntaxes, path, content)?;
+    let mut highlighter = HighlightLines::new(syntax, theme);
+
+    let mut lines = Vec::new();
+    for line in LinesWithEndings::from(content) {
+        let regions = highlighter.highlight_line(line, syntaxes).ok()?;
+        let spans: Vec<Span<'static>> = regions
+            .into_iter()
+            .map(|(style, text)| {
+                let color = Color::Rgb(style.foreground.r, style.foreground.g, style.foreground.b);
+                Span::styled(
+                    text.trim_end_matches(['\n', '\r']).to_string(),
+                    Style::new().fg(color),
+                )
+            })
+            .collect();
+        lines.push(Line::from(spans));
+    }
+    Some(lines)
+}
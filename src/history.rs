@@ -0,0 +1,213 @@
+use std::{
+    fs,
+    io::{Error, ErrorKind, Result},
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use ratatui::widgets::ListState;
+
+use crate::constant::data_file;
+
+const FRECENCY_FILE: &str = "frecency";
+const MAX_AGE: f64 = 10_000.0;
+const SAVE_INTERVAL: Duration = Duration::from_secs(30);
+const HOUR: u64 = 60 * 60;
+const DAY: u64 = 24 * HOUR;
+const WEEK: u64 = 7 * DAY;
+
+#[derive(Default)]
+pub struct History {
+    back: Vec<(PathBuf, Option<usize>)>,
+    forward: Vec<(PathBuf, Option<usize>)>,
+}
+
+impl History {
+    pub fn visit(&mut self, path: PathBuf, index: Option<usize>) {
+        self.back.push((path, index));
+        self.forward.clear();
+    }
+
+    pub fn back(
+        &mut self,
+        current: PathBuf,
+        index: Option<usize>,
+    ) -> Option<(PathBuf, Option<usize>)> {
+        let target = pop_existing(&mut self.back)?;
+        self.forward.push((current, index));
+        Some(target)
+    }
+
+    pub fn forward(
+        &mut self,
+        current: PathBuf,
+        index: Option<usize>,
+    ) -> Option<(PathBuf, Option<usize>)> {
+        let target = pop_existing(&mut self.forward)?;
+        self.back.push((current, index));
+        Some(target)
+    }
+}
+
+fn pop_existing(stack: &mut Vec<(PathBuf, Option<usize>)>) -> Option<(PathBuf, Option<usize>)> {
+    while let Some((path, index)) = stack.pop() {
+        if path.is_dir() {
+            return Some((path, index));
+        }
+    }
+    None
+}
+
+pub struct FrecencyEntry {
+    pub path: PathBuf,
+    rank: f64,
+    last_access: u64,
+}
+
+impl FrecencyEntry {
+    fn score(&self, now: u64) -> f64 {
+        let age = now.saturating_sub(self.last_access);
+        let factor = if age < HOUR {
+            4.0
+        } else if age < DAY {
+            2.0
+        } else if age < WEEK {
+            0.5
+        } else {
+            0.25
+        };
+        self.rank * factor
+    }
+}
+
+#[derive(Default)]
+pub struct Frecency {
+    pub entries: Vec<FrecencyEntry>,
+    pub matches: Vec<PathBuf>,
+    pub state: ListState,
+    last: PathBuf,
+    dirty: bool,
+    saved: Option<Instant>,
+}
+
+impl Frecency {
+    pub fn load() -> Self {
+        let mut frecency = Frecency::default();
+        let Some(content) = data_file(FRECENCY_FILE).and_then(|path| fs::read_to_string(path).ok())
+        else {
+            return frecency;
+        };
+        for line in content.lines() {
+            let mut fields = line.splitn(3, '\t');
+            let (Some(rank), Some(last_access), Some(path)) =
+                (fields.next(), fields.next(), fields.next())
+            else {
+                continue;
+            };
+            if let (Ok(rank), Ok(last_access)) = (rank.parse(), last_access.parse()) {
+                frecency.entries.push(FrecencyEntry {
+                    path: PathBuf::from(path),
+                    rank,
+                    last_access,
+                });
+            }
+        }
+        frecency
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = data_file(FRECENCY_FILE)
+            .ok_or_else(|| Error::new(ErrorKind::NotFound, "no data directory"))?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let content: String = self
+            .entries
+            .iter()
+            .map(|entry| {
+                format!(
+                    "{}\t{}\t{}\n",
+                    entry.rank,
+                    entry.last_access,
+                    entry.path.to_string_lossy()
+                )
+            })
+            .collect();
+        fs::write(path, content)
+    }
+
+    pub fn observe(&mut self, pwd: &Path) {
+        if pwd.as_os_str().is_empty() || self.last == pwd {
+            return;
+        }
+        self.last = pwd.to_path_buf();
+        let now = now();
+        match self.entries.iter_mut().find(|entry| entry.path == pwd) {
+            Some(entry) => {
+                entry.rank += 1.0;
+                entry.last_access = now;
+            }
+            None => self.entries.push(FrecencyEntry {
+                path: pwd.to_path_buf(),
+                rank: 1.0,
+                last_access: now,
+            }),
+        }
+
+        let total: f64 = self.entries.iter().map(|entry| entry.rank).sum();
+        if total > MAX_AGE {
+            for entry in self.entries.iter_mut() {
+                entry.rank *= 0.9;
+            }
+            self.entries.retain(|entry| entry.rank >= 1.0);
+        }
+        self.dirty = true;
+    }
+
+    /// Saves observed visits at most once per [`SAVE_INTERVAL`], or right away with `force`.
+    pub fn flush(&mut self, force: bool) -> Result<()> {
+        let recent = self
+            .saved
+            .is_some_and(|saved| saved.elapsed() < SAVE_INTERVAL);
+        if !self.dirty || (recent && !force) {
+            return Ok(());
+        }
+        self.dirty = false;
+        self.saved = Some(Instant::now());
+        self.save()
+    }
+
+    pub fn filter(&mut self, query: &str) {
+        let now = now();
+        let terms: Vec<String> = query
+            .split_whitespace()
+            .map(|term| term.to_lowercase())
+            .collect();
+        let mut ranked: Vec<(f64, &PathBuf)> = self
+            .entries
+            .iter()
+            .filter(|entry| {
+                let path = entry.path.to_string_lossy().to_lowercase();
+                terms.iter().all(|term| path.contains(term.as_str())) && entry.path.is_dir()
+            })
+            .map(|entry| (entry.score(now), &entry.path))
+            .collect();
+        ranked.sort_by(|a, b| b.0.total_cmp(&a.0));
+        self.matches = ranked
+            .into_iter()
+            .map(|(_, path)| path.to_path_buf())
+            .collect();
+        self.state.select((!self.matches.is_empty()).then_some(0));
+    }
+
+    pub fn selected(&self) -> Option<&PathBuf> {
+        self.matches.get(self.state.selected()?)
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
@@ -0,0 +1,138 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    thread,
+    time::SystemTime,
+};
+
+use image::{imageops::FilterType, ImageFormat, ImageReader, RgbaImage};
+use ratatui::{buffer::Buffer, layout::Rect, style::Color};
+use tokio::sync::mpsc::Sender;
+
+use crate::{archive, explorer::FileStruct};
+
+const THUMBNAIL_SIZE: u32 = 512;
+const MAX_CACHED_FILES: usize = 64;
+const SUPPORTED: [ImageFormat; 4] = [
+    ImageFormat::Png,
+    ImageFormat::Jpeg,
+    ImageFormat::Gif,
+    ImageFormat::WebP,
+];
+
+type Stamp = (PathBuf, SystemTime);
+type Decoded = Result<Arc<Picture>, String>;
+type Cache = HashMap<PathBuf, (SystemTime, Decoded)>;
+
+pub struct Picture {
+    pub format: String,
+    pub width: u32,
+    pub height: u32,
+    thumbnail: RgbaImage,
+}
+
+impl Picture {
+    pub fn render(&self, area: Rect, buf: &mut Buffer) {
+        if area.is_empty() || self.width == 0 || self.height == 0 {
+            return;
+        }
+        let max_width = area.width as f64;
+        let max_height = area.height as f64 * 2.0;
+        let scale = (max_width / self.width as f64).min(max_height / self.height as f64);
+        let width = ((self.width as f64 * scale) as u32).clamp(1, area.width as u32);
+        let height = ((self.height as f64 * scale) as u32).clamp(1, area.height as u32 * 2);
+        let pixels = image::imageops::resize(&self.thumbnail, width, height, FilterType::Triangle);
+
+        let left = area.x + (area.width - width as u16) / 2;
+        let top = area.y + (area.height - height.div_ceil(2) as u16) / 2;
+        for row in 0..height.div_ceil(2) {
+            for column in 0..width {
+                let upper = pixel_color(&pixels, column, row * 2);
+                let lower = pixel_color(&pixels, column, row * 2 + 1);
+                buf[(left + column as u16, top + row as u16)]
+                    .set_char('▀')
+                    .set_fg(upper)
+                    .set_bg(lower);
+            }
+        }
+    }
+}
+
+fn pixel_color(pixels: &RgbaImage, x: u32, y: u32) -> Color {
+    match pixels.get_pixel_checked(x, y) {
+        Some(pixel) if pixel[3] >= 128 => Color::Rgb(pixel[0], pixel[1], pixel[2]),
+        _ => Color::Reset,
+    }
+}
+
+pub fn is_image(path: &Path) -> bool {
+    ImageFormat::from_path(path).is_ok_and(|format| SUPPORTED.contains(&format))
+}
+
+#[derive(Default)]
+pub struct ImagePreview {
+    cache: Arc<Mutex<Cache>>,
+    pending: Arc<Mutex<HashSet<Stamp>>>,
+}
+
+impl ImagePreview {
+    pub fn get(&self, stamp: &Stamp) -> Option<Decoded> {
+        let cache = self.cache.lock().unwrap();
+        match cache.get(&stamp.0) {
+            Some((modified, decoded)) if *modified == stamp.1 => Some(decoded.clone()),
+            _ => None,
+        }
+    }
+
+    pub fn sync(&mut self, files: &Arc<Mutex<FileStruct>>, tx: &Sender<String>) {
+        let stamp = {
+            let file_struct = files.lock().unwrap();
+            let Some(stamp) = file_struct.content_stamp.clone() else {
+                return;
+            };
+            if !is_image(&stamp.0) || self.get(&stamp).is_some() {
+                return;
+            }
+            if !self.pending.lock().unwrap().insert(stamp.clone()) {
+                return;
+            }
+            stamp
+        };
+
+        let cache = Arc::clone(&self.cache);
+        let pending = Arc::clone(&self.pending);
+        let tx = tx.clone();
+        thread::spawn(move || {
+            let decoded = decode(&stamp.0).map(Arc::new);
+            {
+                let mut cache = cache.lock().unwrap();
+                if cache.len() >= MAX_CACHED_FILES {
+                    cache.clear();
+                }
+                cache.insert(stamp.0.to_path_buf(), (stamp.1, decoded));
+            }
+            pending.lock().unwrap().remove(&stamp);
+            let _ = tx.try_send(String::new());
+        });
+    }
+}
+
+fn decode(path: &Path) -> Result<Picture, String> {
+    archive::materialize(&[path.to_path_buf()]).map_err(|error| error.to_string())?;
+    let reader = ImageReader::open(path)
+        .and_then(|reader| reader.with_guessed_format())
+        .map_err(|error| error.to_string())?;
+    let format = reader
+        .format()
+        .and_then(|format| format.extensions_str().first())
+        .map(|extension| extension.to_uppercase())
+        .unwrap_or_default();
+    let image = reader.decode().map_err(|error| error.to_string())?;
+    Ok(Picture {
+        format,
+        width: image.width(),
+        height: image.height(),
+        thumbnail: image.thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE).to_rgba8(),
+    })
+}
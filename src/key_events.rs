@@ -1,14 +1,22 @@
 use std::{
-    io::{Error, ErrorKind},
-    sync::{Arc, MutexGuard},
+    fs,
+    io::{Error, ErrorKind, Result},
+    path::PathBuf,
+    sync::{Arc, Mutex, MutexGuard},
     thread,
 };
 
 use crate::{
+    archive::{self, ArchiveKind, Progress},
+    clipboard,
     constant::COLORS,
     crypto_handler::AesEncryptor,
-    explorer::FileStruct,
-    ui::{FileScout, ViewMode},
+    explorer::{file_stamp, FileStruct, HEX_WIDTH},
+    external::{ExternalCommand, ExternalKind},
+    path_prompt::PathPrompt,
+    shell,
+    text_buffer::{Cursor, TextBuffer},
+    ui::{Confirm, FileScout, Input, ViewMode},
 };
 use crossterm::event::{KeyCode, KeyModifiers};
 use tokio::sync::mpsc::Sender;
@@ -21,31 +29,203 @@ pub fn handle_events(
 ) {
     let file_clone = Arc::clone(&file.files);
     let mut file_struct = file_clone.lock().unwrap();
+    file_struct.status = None;
 
     match file.mode {
         ViewMode::FileEdit => handle_file_edit(code, file, file_struct, modifier),
-        ViewMode::Create | ViewMode::Rename => handle_file_name(code, file, file_struct),
+        ViewMode::Create | ViewMode::Rename | ViewMode::CreateArchive => {
+            handle_file_name(code, file, file_struct, tx)
+        }
+        ViewMode::Search => handle_search_input(code, file, file_struct, modifier, tx),
+        ViewMode::SearchResults => handle_search_results(code, file, file_struct),
+        ViewMode::MarkSet | ViewMode::MarkJump => handle_mark(code, file, file_struct),
+        ViewMode::Bookmarks => handle_bookmarks(code, file, file_struct),
+        ViewMode::BookmarkRename => handle_bookmark_rename(code, file, file_struct),
+        ViewMode::Jump => handle_jump(code, file, file_struct),
+        ViewMode::GoTo => handle_goto(code, file, file_struct),
+        ViewMode::GoToOffset => handle_goto_offset(code, file, file_struct),
+        ViewMode::Find => handle_find(code, file, file_struct, modifier),
+        ViewMode::Replace => handle_replace(code, file, modifier),
+        ViewMode::DiskUsage => handle_disk_usage(code, file, file_struct),
+        ViewMode::Duplicates => handle_duplicates(code, file, file_struct),
+        ViewMode::OpenWith => handle_open_with(code, file, file_struct),
+        ViewMode::Shell => handle_shell(code, file, file_struct, tx),
+        ViewMode::ShellLog => handle_shell_log(code, file),
         _ => match code {
             KeyCode::Char('q') | KeyCode::Char('Q') => file.exit = true,
             KeyCode::Char('r') | KeyCode::Char('R') => {
+                if !ensure_writable(&mut file_struct) {
+                    return;
+                }
                 if let Some(path) = &file_struct.current_path {
                     file.input.content = path.file_name().unwrap().to_str().unwrap().to_string();
                     file.mode = ViewMode::Rename
                 }
             }
-            KeyCode::Char('n') | KeyCode::Char('N') => file.mode = ViewMode::Create,
+            KeyCode::Char('/') if file.mode == ViewMode::ContentView => {
+                open_find(file, &mut file_struct)
+            }
+            KeyCode::Char(key @ ('n' | 'N'))
+                if file.mode == ViewMode::ContentView && file.finder.is_active() =>
+            {
+                let from = match &file.finder.current {
+                    Some((line, range)) => (*line, range.start),
+                    None => (file.text_scroll_y, 0),
+                };
+                let inclusive = file.finder.current.is_none();
+                find_in_content(file, &file_struct, from, key == 'n', inclusive);
+            }
+            KeyCode::Esc if file.mode == ViewMode::ContentView => file.finder.clear(),
+            KeyCode::Char('n') | KeyCode::Char('N') => {
+                if !ensure_writable(&mut file_struct) {
+                    return;
+                }
+                file.mode = ViewMode::Create
+            }
+            KeyCode::Char(' ') if file.mode == ViewMode::ListView => file_struct.toggle_mark(),
+            KeyCode::Char('x') | KeyCode::Char('X') => {
+                extract_selection(file, &mut file_struct, tx)
+            }
+            KeyCode::Char('a') | KeyCode::Char('A') => {
+                if !ensure_writable(&mut file_struct) {
+                    return;
+                }
+                if file_struct.selection().is_empty() {
+                    return;
+                }
+                file.input.content.clear();
+                file.mode = ViewMode::CreateArchive
+            }
+            KeyCode::Char('s') | KeyCode::Char('S') => file.mode = ViewMode::Search,
+            KeyCode::Char('m') | KeyCode::Char('M') => file.mode = ViewMode::MarkSet,
+            KeyCode::Char('\'') => file.mode = ViewMode::MarkJump,
+            KeyCode::Char('g') | KeyCode::Char('G') => {
+                file.input.content.clear();
+                file.path_prompt.candidates.clear();
+                file.mode = ViewMode::GoTo
+            }
+            KeyCode::Char('t') | KeyCode::Char('T') => file.open_tab(&file_struct),
+            KeyCode::Char('k') | KeyCode::Char('K') => file.close_tab(),
+            KeyCode::Char(digit @ '1'..='9') => {
+                file.switch_tab(digit as usize - '1' as usize);
+            }
+            KeyCode::Char('l') | KeyCode::Char('L') => file.toggle_layout(&file_struct),
+            KeyCode::BackTab => file.switch_pane(),
+            KeyCode::F(5) => transfer(file, &mut file_struct, tx, false),
+            KeyCode::F(6) => transfer(file, &mut file_struct, tx, true),
+            KeyCode::Char('f') | KeyCode::Char('F') if file.mode == ViewMode::ContentView => {
+                file_struct.toggle_follow()
+            }
+            KeyCode::Char(':') if file.mode == ViewMode::ContentView => {
+                file.input.content.clear();
+                file.mode = ViewMode::GoToOffset
+            }
+            KeyCode::Char('v') | KeyCode::Char('V') => {
+                if !ensure_writable(&mut file_struct) {
+                    return;
+                }
+                open_external(file, &mut file_struct, ExternalKind::Editor)
+            }
+            KeyCode::Char('p') | KeyCode::Char('P') => {
+                open_external(file, &mut file_struct, ExternalKind::Pager)
+            }
+            KeyCode::Char('w') | KeyCode::Char('W') => {
+                let Some(files) = readable_selection(&mut file_struct) else {
+                    return;
+                };
+                match file.open_with.prepare(files, file.mode) {
+                    Ok(()) => file.mode = ViewMode::OpenWith,
+                    Err(error) => file_struct.error = Some(error),
+                }
+            }
+            KeyCode::Char('!') => {
+                file.input.content.clear();
+                file.mode = ViewMode::Shell
+            }
+            KeyCode::Char('j') | KeyCode::Char('J') => {
+                file.shell.back = 0;
+                file.mode = ViewMode::ShellLog
+            }
+            KeyCode::Enter => match file_struct.current_path.clone() {
+                Some(path) if path.is_dir() => {
+                    if file.mode == ViewMode::ListView {
+                        file_struct.present_dir_fn(&path, None)
+                    }
+                }
+                Some(path)
+                    if file.mode == ViewMode::ListView && ArchiveKind::of(&path).is_some() =>
+                {
+                    open_archive(file, &mut file_struct, tx, path)
+                }
+                _ => {
+                    let Some(files) = readable_selection(&mut file_struct) else {
+                        return;
+                    };
+                    let launched = file
+                        .open_with
+                        .default_rule(&files)
+                        .and_then(|rule| rule.launch(&files));
+                    start_launched(file, &mut file_struct, launched);
+                }
+            },
+            KeyCode::Char('u') | KeyCode::Char('U') => {
+                file_struct.error = None;
+                file.disk_usage.open(&file_struct.pwd);
+                file.mode = ViewMode::DiskUsage
+            }
+            KeyCode::Char('=') => {
+                file_struct.error = None;
+                let pwd = file_struct.pwd.to_path_buf();
+                file.duplicates.start(&pwd, tx);
+                file.mode = ViewMode::Duplicates
+            }
+            KeyCode::Char('[') => file_struct.go_back(),
+            KeyCode::Char(']') => file_struct.go_forward(),
+            KeyCode::Char('z') | KeyCode::Char('Z') => {
+                file.input.content.clear();
+                file.frecency.filter("");
+                file.mode = ViewMode::Jump
+            }
+            KeyCode::Char('b') | KeyCode::Char('B') => {
+                if file.bookmarks.state.selected().is_none() {
+                    file.bookmarks.state.select_first();
+                }
+                file.mode = ViewMode::Bookmarks
+            }
             KeyCode::Char('o') | KeyCode::Char('O') => {
+                if !ensure_writable(&mut file_struct) {
+                    return;
+                }
+                let path = file_struct.current_path.clone().unwrap_or_default();
+                let stamp = file_stamp(&path);
                 match file_struct.file_read() {
-                    Ok(content) => {
-                        file.input.total_lines = content.lines().count();
-                        file.input.total_letter = content.lines().next().unwrap_or_default().len();
-                        file.input.content = content;
+                    Ok((content, format)) => {
+                        file.input.path = path;
+                        file.input.stamp = stamp;
+                        file.input.status = format.lossy.then(|| {
+                            format!(
+                                "Invalid {} bytes were replaced, saving is disabled",
+                                format.encoding.name()
+                            )
+                        });
+                        file.input.confirm = None;
+                        file.input.anchor = None;
+                        file.input.format = format;
+                        file.input.buffer = TextBuffer::new(&content);
+                        file.input.saved = file.input.buffer.revision();
+                        file.input.top = 0;
+                        file.input.left = 0;
+                        file.text_scroll_y = 0;
+                        file.text_scroll_x = 0;
                         file.mode = ViewMode::FileEdit;
                     }
                     Err(error) => file_struct.error = Some(error),
                 };
             }
             KeyCode::Char('e') | KeyCode::Char('E') => {
+                if !ensure_writable(&mut file_struct) {
+                    return;
+                }
                 if let Some(index) = file_struct.current_state.selected() {
                     let path = file_struct.current_dir[index].to_path_buf();
                     if path.is_file() {
@@ -67,10 +247,7 @@ pub fn handle_events(
                                             .try_send(String::from("File Encryption completed"))
                                             .is_err()
                                         {
-                                            msg.error = Some(Error::new(
-                                                ErrorKind::Other,
-                                                "Failed to refresh",
-                                            ))
+                                            msg.error = Some(Error::other("Failed to refresh"))
                                         }
                                     }
                                     Err(error) => {
@@ -80,10 +257,7 @@ pub fn handle_events(
                                             .try_send(String::from("Failed to Encrypt file"))
                                             .is_err()
                                         {
-                                            msg.error = Some(Error::new(
-                                                ErrorKind::Other,
-                                                "Failed to refresh",
-                                            ))
+                                            msg.error = Some(Error::other("Failed to refresh"))
                                         }
                                     }
                                 }
@@ -98,6 +272,9 @@ pub fn handle_events(
                 }
             }
             KeyCode::Char('d') | KeyCode::Char('D') => {
+                if !ensure_writable(&mut file_struct) {
+                    return;
+                }
                 if let Some(index) = file_struct.current_state.selected() {
                     let path = file_struct.current_dir[index].to_path_buf();
                     if path.is_file() {
@@ -123,10 +300,7 @@ pub fn handle_events(
                                             .try_send(String::from("File Decryption completed"))
                                             .is_err()
                                         {
-                                            msg.error = Some(Error::new(
-                                                ErrorKind::Other,
-                                                "Failed to refresh",
-                                            ))
+                                            msg.error = Some(Error::other("Failed to refresh"))
                                         }
                                     }
                                     Err(error) => {
@@ -136,10 +310,7 @@ pub fn handle_events(
                                             .try_send(String::from("Failed to Decrypt file"))
                                             .is_err()
                                         {
-                                            msg.error = Some(Error::new(
-                                                ErrorKind::Other,
-                                                "Failed to refresh",
-                                            ))
+                                            msg.error = Some(Error::other("Failed to refresh"))
                                         }
                                     }
                                 }
@@ -155,6 +326,9 @@ pub fn handle_events(
                 file.color_index = (file.color_index + 1) % COLORS.len()
             }
             KeyCode::Delete => {
+                if !ensure_writable(&mut file_struct) {
+                    return;
+                }
                 if let Some(index) = file_struct.current_state.selected() {
                     let path = file_struct.current_dir[index].to_path_buf();
                     FileStruct::delete(path.as_path(), &mut file_struct);
@@ -190,14 +364,16 @@ pub fn handle_events(
                             file_struct.next_dir_fn(path.as_path());
                         } else if file_struct.current_dir.len() > index {
                             file_struct.content = String::new();
+                            file_struct.lines.clear();
+                            file_struct.binary = None;
+                            file_struct.large = None;
                             let file_path = file_struct.current_dir[index].to_path_buf();
                             let file = Arc::clone(&file.files);
                             tokio::spawn(async move {
                                 let mut file_str = file.lock().unwrap();
                                 file_str.read_file(file_path);
                                 if tx.try_send(String::new()).is_err() {
-                                    file_str.error =
-                                        Some(Error::new(ErrorKind::Other, "something went wrong"))
+                                    file_str.error = Some(Error::other("something went wrong"))
                                 }
                             });
                         } else {
@@ -207,10 +383,10 @@ pub fn handle_events(
                             Some(file_struct.current_dir[index].to_path_buf());
                     }
                 }
-                ViewMode::ContentView => {
-                    if file.text_scroll_y < file_struct.line_count.saturating_sub(1) {
-                        file.text_scroll_y = file.text_scroll_y.saturating_add(1)
-                    }
+                ViewMode::ContentView
+                    if file.text_scroll_y < file_struct.total_lines().saturating_sub(1) =>
+                {
+                    file.text_scroll_y = file.text_scroll_y.saturating_add(1)
                 }
                 _ => {}
             },
@@ -227,14 +403,16 @@ pub fn handle_events(
                             file_struct.next_dir_fn(path.as_path());
                         } else if file_struct.current_dir.len() > index {
                             file_struct.content = String::new();
+                            file_struct.lines.clear();
+                            file_struct.binary = None;
+                            file_struct.large = None;
                             let file_path = file_struct.current_dir[index].to_path_buf();
                             let file = Arc::clone(&file.files);
                             tokio::spawn(async move {
                                 let mut file_str = file.lock().unwrap();
                                 file_str.read_file(file_path);
                                 if tx.try_send(String::new()).is_err() {
-                                    file_str.error =
-                                        Some(Error::new(ErrorKind::Other, "something went wrong"))
+                                    file_str.error = Some(Error::other("something went wrong"))
                                 }
                             });
                         }
@@ -253,6 +431,13 @@ pub fn handle_events(
                         {
                             let path = file_struct.current_dir[index].to_path_buf();
                             file_struct.present_dir_fn(path.as_path(), None);
+                        } else if let Some(path) = file_struct
+                            .current_dir
+                            .get(index)
+                            .filter(|path| ArchiveKind::of(path).is_some())
+                            .cloned()
+                        {
+                            open_archive(file, &mut file_struct, tx, path);
                         }
                     }
                 }
@@ -261,7 +446,9 @@ pub fn handle_events(
             },
             KeyCode::Left => match file.mode {
                 ViewMode::ListView => {
-                    if let Some(index) = file_struct.parent_state.selected() {
+                    let pwd = archive::entry_of(&file_struct.pwd);
+                    if let Some(index) = file_struct.parent_dir.iter().position(|path| *path == pwd)
+                    {
                         let path = file_struct.parent.to_path_buf();
                         file_struct.present_dir_fn(path.as_path(), Some(index));
                     }
@@ -274,118 +461,581 @@ pub fn handle_events(
     }
 }
 
+fn transfer(file: &FileScout, file_struct: &mut FileStruct, tx: Sender<String>, is_move: bool) {
+    let Some(target) = file.other_tab() else {
+        file_struct.error = Some(Error::new(
+            ErrorKind::NotFound,
+            "open another tab to copy or move to",
+        ));
+        return;
+    };
+    if (is_move && !ensure_writable(file_struct)) || target.lock().unwrap().in_archive() {
+        file_struct.error = Some(read_only());
+        return;
+    }
+    let dest = target.lock().unwrap().pwd.to_path_buf();
+    let paths = file_struct.selection();
+    file_struct.marked.clear();
+    let source = Arc::clone(&file.files);
+    thread::spawn(move || {
+        let result = archive::materialize(&paths).and_then(|()| {
+            paths.iter().try_for_each(|path| {
+                if is_move {
+                    FileStruct::move_to(path, &dest)
+                } else {
+                    FileStruct::copy_to(path, &dest)
+                }
+            })
+        });
+        target.lock().unwrap().refresh();
+        let mut msg = source.lock().unwrap();
+        msg.refresh();
+        if let Err(error) = result {
+            msg.error = Some(error);
+        }
+        if tx.try_send(String::from("Transfer completed")).is_err() {
+            msg.error = Some(Error::other("Failed to refresh"))
+        }
+    });
+}
+
+fn open_external(file: &mut FileScout, file_struct: &mut FileStruct, kind: ExternalKind) {
+    let Some(files) = readable_selection(file_struct) else {
+        return;
+    };
+    let files: Vec<PathBuf> = files.into_iter().filter(|path| path.is_file()).collect();
+    if files.is_empty() {
+        file_struct.error = Some(Error::new(ErrorKind::InvalidInput, "select a file to open"));
+        return;
+    }
+    match ExternalCommand::new(kind, files) {
+        Ok(command) => file.external = Some(command),
+        Err(error) => file_struct.error = Some(error),
+    }
+}
+
+/// The selected or marked paths, with any pending archive entries among them extracted.
+fn readable_selection(file_struct: &mut FileStruct) -> Option<Vec<PathBuf>> {
+    let paths = file_struct.selection();
+    if paths.is_empty() {
+        return None;
+    }
+    match archive::materialize(&paths) {
+        Ok(()) => Some(paths),
+        Err(error) => {
+            file_struct.error = Some(error);
+            None
+        }
+    }
+}
+
+fn start_launched(
+    file: &mut FileScout,
+    file_struct: &mut FileStruct,
+    launched: Result<Option<ExternalCommand>>,
+) {
+    match launched {
+        Ok(Some(command)) => file.external = Some(command),
+        Ok(None) => {}
+        Err(error) => file_struct.error = Some(error),
+    }
+}
+
+fn ensure_writable(file_struct: &mut FileStruct) -> bool {
+    if file_struct.in_archive() {
+        file_struct.error = Some(read_only());
+        return false;
+    }
+    true
+}
+
+fn read_only() -> Error {
+    Error::new(
+        ErrorKind::PermissionDenied,
+        "archive contents are read-only, extract them with <X>",
+    )
+}
+
+fn archive_job<F>(
+    file: &FileScout,
+    file_struct: &mut FileStruct,
+    tx: Sender<String>,
+    label: String,
+    job: F,
+) where
+    F: FnOnce(&Mutex<Option<Progress>>) -> Result<Option<PathBuf>> + Send + 'static,
+{
+    let task = Arc::clone(&file.archive_task);
+    {
+        let mut progress = task.lock().unwrap();
+        if progress.is_some() {
+            file_struct.error = Some(Error::other("another archive job is still running"));
+            return;
+        }
+        *progress = Some(Progress::new(label));
+    }
+    let files = Arc::clone(&file.files);
+    thread::spawn(move || {
+        let result = job(&task);
+        let mut file_struct = files.lock().unwrap();
+        match result {
+            Ok(Some(root)) => file_struct.present_dir_fn(&root, None),
+            Ok(None) => file_struct.refresh(),
+            Err(error) => {
+                file_struct.refresh();
+                file_struct.error = Some(error);
+            }
+        }
+        *task.lock().unwrap() = None;
+        let _ = tx.try_send(String::new());
+    });
+}
+
+fn open_archive(file: &FileScout, file_struct: &mut FileStruct, tx: Sender<String>, path: PathBuf) {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let label = format!("Opening {}", name);
+    archive_job(file, file_struct, tx, label, move |task| {
+        archive::mount(&path, task).map(Some)
+    });
+}
+
+fn extract_selection(file: &FileScout, file_struct: &mut FileStruct, tx: Sender<String>) {
+    let paths = file_struct.selection();
+    if file_struct.in_archive() {
+        let host = archive::host_of(&file_struct.pwd);
+        let Some(dest) = host.parent().map(|parent| parent.to_path_buf()) else {
+            return;
+        };
+        let label = format!("Extracting to {}", dest.display());
+        file_struct.marked.clear();
+        archive_job(file, file_struct, tx, label, move |task| {
+            archive::copy_out(&paths, &dest, task).map(|_| None)
+        });
+        return;
+    }
+
+    let archives: Vec<PathBuf> = paths
+        .into_iter()
+        .filter(|path| path.is_file() && ArchiveKind::of(path).is_some())
+        .collect();
+    if archives.is_empty() {
+        file_struct.error = Some(Error::new(ErrorKind::InvalidInput, "no archive selected"));
+        return;
+    }
+    let label = format!("Extracting {} archive(s)", archives.len());
+    file_struct.marked.clear();
+    archive_job(file, file_struct, tx, label, move |task| {
+        archives
+            .iter()
+            .try_for_each(|path| archive::extract_here(path, task))
+            .map(|_| None)
+    });
+}
+
+fn create_archive(file: &FileScout, file_struct: &mut FileStruct, tx: Sender<String>) {
+    let name = file.input.content.trim().to_string();
+    if name.is_empty() {
+        return;
+    }
+    let dest = file_struct.pwd.join(&name);
+    let paths = file_struct.selection();
+    file_struct.marked.clear();
+    archive_job(
+        file,
+        file_struct,
+        tx,
+        format!("Creating {}", name),
+        move |task| archive::create(&dest, &paths, task).map(|_| None),
+    );
+}
+
 fn handle_file_edit(
     code: KeyCode,
     file: &mut FileScout,
     mut file_struct: MutexGuard<FileStruct>,
     modifier: KeyModifiers,
 ) {
-    let mut line = file.input.content.lines();
-    match (code, modifier) {
-        (KeyCode::Char('s'), KeyModifiers::CONTROL) => {
-            file_struct.file_write(file.input.content.clone());
-            let pwd = file_struct.pwd.to_path_buf();
-            reset_mode(file);
-            if let Some(index) = file_struct.current_state.selected() {
-                file_struct.present_dir_fn(&pwd, Some(index));
+    if let Some(confirm) = file.input.confirm.take() {
+        match (confirm, code) {
+            (Confirm::Quit, KeyCode::Char('y' | 'Y')) => {
+                if save_edit(file, &mut file_struct, false, true) {
+                    close_editor(file);
+                }
             }
-            file.text_scroll_y = 0;
-            file.text_scroll_x = 0;
-        }
-        (KeyCode::Char('c'), KeyModifiers::CONTROL) => {
-            reset_mode(file);
-            file.text_scroll_y = 0;
-            file.text_scroll_x = 0;
+            (Confirm::Quit, KeyCode::Char('n' | 'N')) => close_editor(file),
+            (Confirm::Overwrite { quit }, KeyCode::Char('y' | 'Y')) => {
+                if save_edit(file, &mut file_struct, true, quit) && quit {
+                    close_editor(file);
+                }
+            }
+            (_, KeyCode::Char('n' | 'N') | KeyCode::Esc) => {}
+            (confirm, _) => file.input.confirm = Some(confirm),
         }
-        (KeyCode::Char(ch), _) => {
-            edit_content(file, ch);
-            file.text_scroll_x = file.text_scroll_x.saturating_add(1)
+        return;
+    }
+    file.input.status = None;
+
+    let cursor = (file.text_scroll_y, file.text_scroll_x);
+    if let Some(target) = motion(&file.input, cursor, code, modifier) {
+        file.input.buffer.break_undo_group();
+        if modifier.contains(KeyModifiers::SHIFT) {
+            file.input.anchor.get_or_insert(cursor);
+        } else {
+            file.input.anchor = None;
         }
-        (KeyCode::Enter, _) => {
-            edit_content(file, '\n');
-            file.text_scroll_y = file.text_scroll_y.saturating_add(1);
-            file.text_scroll_x = 0;
-            file.input.content.push_str("");
+        (file.text_scroll_y, file.text_scroll_x) = target;
+        return;
+    }
+
+    let selection = file.input.selection(cursor);
+    let line_ending = file.input.format.line_ending.as_str();
+    let buffer = &mut file.input.buffer;
+    let edited = match (code, modifier) {
+        (KeyCode::Char('s'), KeyModifiers::CONTROL) => {
+            save_edit(file, &mut file_struct, false, false);
+            return;
         }
-        (KeyCode::Backspace, _) => {
-            remove_at(file);
-            file.text_scroll_x = file.text_scroll_x.saturating_sub(1);
+        (KeyCode::Char('c'), KeyModifiers::CONTROL) => {
+            if file.input.dirty() {
+                file.input.confirm = Some(Confirm::Quit);
+            } else {
+                close_editor(file);
+            }
+            return;
         }
-        (KeyCode::Tab, _) => {
-            edit_content(file, ' ');
-            file.text_scroll_x = file.text_scroll_x.saturating_add(1);
+        (KeyCode::Char('f'), KeyModifiers::CONTROL) => {
+            open_find(file, &mut file_struct);
+            return;
         }
-        (KeyCode::Down, _) => {
-            let mut lines = file.input.content.lines();
-            if file.text_scroll_y < lines.clone().count().saturating_sub(1) {
-                file.text_scroll_y = file.text_scroll_y.saturating_add(1);
-                file.input.total_letter = lines.nth(file.text_scroll_y).unwrap_or_default().len();
-                if file.text_scroll_x > file.input.total_letter {
-                    file.text_scroll_x = file.input.total_letter
-                }
+        (KeyCode::Char('r'), KeyModifiers::CONTROL) => {
+            if !file.finder.is_active() {
+                open_find(file, &mut file_struct);
+                return;
             }
+            file.input.content = file.finder.replacement.to_string();
+            file.finder.origin = Some((file.mode, file.text_scroll_y, file.text_scroll_x));
+            file.mode = ViewMode::Replace;
+            return;
         }
-        (KeyCode::Up, _) => {
-            file.text_scroll_y = file.text_scroll_y.saturating_sub(1);
-            file.input.total_letter = line.nth(file.text_scroll_y).unwrap_or_default().len();
-            if file.text_scroll_x > file.input.total_letter {
-                file.text_scroll_x = file.input.total_letter
-            }
+        (KeyCode::Char(key @ ('n' | 'p')), KeyModifiers::CONTROL) => {
+            find_in_editor(file, cursor, key == 'n', false);
+            file.input.anchor = None;
+            return;
         }
-        (KeyCode::Left, _) => {
-            if file.text_scroll_x == 0 && file.text_scroll_y != 0 {
-                file.text_scroll_y = file.text_scroll_y.saturating_sub(1);
-                file.input.total_letter = line.nth(file.text_scroll_y).unwrap_or_default().len();
-                file.text_scroll_x = file.input.total_letter;
-                return;
+        (KeyCode::Esc, _) => {
+            file.finder.clear();
+            file.input.anchor = None;
+            return;
+        }
+        (KeyCode::Char('z'), KeyModifiers::CONTROL) => buffer.undo(),
+        (KeyCode::Char('y'), KeyModifiers::CONTROL) => buffer.redo(),
+        (KeyCode::Char('c'), KeyModifiers::ALT) | (KeyCode::Char('x'), KeyModifiers::CONTROL) => {
+            let cut = code == KeyCode::Char('x');
+            let (text, start, end) = match selection {
+                Some((start, end)) => (buffer.slice(start, end), start, end),
+                None => (
+                    format!("{}{}", buffer.line(cursor.0), line_ending),
+                    (cursor.0, 0),
+                    (cursor.0, buffer.line_len(cursor.0)),
+                ),
+            };
+            let _ = clipboard::copy(&text);
+            file.input.register = text;
+            file.input.register_line = selection.is_none();
+            match (cut, selection) {
+                (false, _) => None,
+                (true, Some(_)) => Some(buffer.remove_range(start, end)),
+                (true, None) => Some(buffer.delete_line(cursor.0)),
             }
-            file.text_scroll_x = file.text_scroll_x.saturating_sub(1);
         }
-        (KeyCode::Right, _) => {
-            if file.text_scroll_x == file.input.total_letter {
-                file.text_scroll_x = 0;
-                file.text_scroll_y = file.text_scroll_y.saturating_add(1);
-                file.input.total_letter = line.nth(file.text_scroll_y).unwrap_or_default().len();
+        (KeyCode::Char('v'), KeyModifiers::CONTROL) => {
+            if file.input.register.is_empty() {
                 return;
             }
-            file.text_scroll_x = file.text_scroll_x.saturating_add(1);
+            let cursor = match selection {
+                Some((start, end)) => buffer.remove_range(start, end),
+                None => cursor,
+            };
+            if file.input.register_line && selection.is_none() {
+                buffer.insert(cursor.0, 0, &file.input.register);
+                Some((cursor.0 + 1, cursor.1))
+            } else {
+                Some(buffer.insert(cursor.0, cursor.1, &file.input.register))
+            }
         }
-        _ => {}
+        (KeyCode::Char('d'), KeyModifiers::CONTROL) => {
+            buffer.duplicate_line(cursor.0, line_ending);
+            Some((cursor.0 + 1, cursor.1))
+        }
+        (KeyCode::Char('k'), KeyModifiers::CONTROL) => Some(buffer.delete_line(cursor.0)),
+        (KeyCode::Up, KeyModifiers::ALT) if cursor.0 > 0 => buffer
+            .swap_lines(cursor.0 - 1)
+            .then_some((cursor.0 - 1, cursor.1)),
+        (KeyCode::Down, KeyModifiers::ALT) => buffer
+            .swap_lines(cursor.0)
+            .then_some((cursor.0 + 1, cursor.1)),
+        (KeyCode::Backspace | KeyCode::Delete, _) if selection.is_some() => {
+            selection.map(|(start, end)| buffer.remove_range(start, end))
+        }
+        (KeyCode::Backspace, _) => buffer.remove_before(cursor.0, cursor.1),
+        (KeyCode::Delete, _) => buffer.remove_after(cursor.0, cursor.1).then_some(cursor),
+        (KeyCode::Char(_) | KeyCode::Enter | KeyCode::Tab, _)
+            if !modifier.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) =>
+        {
+            let text = match code {
+                KeyCode::Char(ch) => ch.to_string(),
+                KeyCode::Enter => line_ending.to_string(),
+                _ => String::from(" "),
+            };
+            let cursor = match selection {
+                Some((start, end)) => buffer.remove_range(start, end),
+                None => cursor,
+            };
+            Some(buffer.insert(cursor.0, cursor.1, &text))
+        }
+        _ => None,
+    };
+    if let Some(cursor) = edited {
+        let line = cursor.0.min(buffer.line_count() - 1);
+        file.text_scroll_y = line;
+        file.text_scroll_x = cursor.1.min(buffer.line_len(line));
+        file.input.anchor = None;
     }
 }
 
-fn edit_content(file: &mut FileScout, ch: char) {
-    let lines = file.input.content.lines();
-    if file.input.content.lines().count() == 0 {
-        file.input.content.insert(0, ch);
-    } else {
-        let mut list: Vec<String> = lines.map(|ln| ln.to_string()).collect();
-        if list.len() > file.text_scroll_y {
-            list[file.text_scroll_y].insert(file.text_scroll_x, ch);
-            file.input.content = list.join("\n");
-        } else {
-            file.input.content.push(ch);
+fn save_edit(file: &mut FileScout, file_struct: &mut FileStruct, force: bool, quit: bool) -> bool {
+    let path = file.input.path.to_path_buf();
+    if !force && file_stamp(&path) != file.input.stamp {
+        file.input.confirm = Some(Confirm::Overwrite { quit });
+        return false;
+    }
+    let content = file.input.buffer.text();
+    if let Err(error) = FileStruct::file_write(&path, &content, &file.input.format) {
+        file.input.status = Some(error.to_string());
+        return false;
+    }
+    file.input.saved = file.input.buffer.revision();
+    file.input.stamp = file_stamp(&path);
+    file.input.status = Some(String::from("Saved"));
+    let pwd = file_struct.pwd.to_path_buf();
+    if let Some(index) = file_struct.current_state.selected() {
+        file_struct.present_dir_fn(&pwd, Some(index));
+    }
+    true
+}
+
+fn close_editor(file: &mut FileScout) {
+    reset_mode(file);
+    file.input.anchor = None;
+    file.text_scroll_y = 0;
+    file.text_scroll_x = 0;
+}
+
+fn motion(input: &Input, cursor: Cursor, code: KeyCode, modifier: KeyModifiers) -> Option<Cursor> {
+    let buffer = &input.buffer;
+    let (line, column) = cursor;
+    let last = buffer.line_count() - 1;
+    let control = modifier.contains(KeyModifiers::CONTROL);
+    if modifier.contains(KeyModifiers::ALT) {
+        return None;
+    }
+    let target = match code {
+        KeyCode::Up => (line.saturating_sub(1), column),
+        KeyCode::Down => (line.saturating_add(1).min(last), column),
+        KeyCode::PageUp => (line.saturating_sub(input.page), column),
+        KeyCode::PageDown => (line.saturating_add(input.page).min(last), column),
+        KeyCode::Home if control => (0, 0),
+        KeyCode::End if control => (last, buffer.line_len(last)),
+        KeyCode::Home => (line, 0),
+        KeyCode::End => (line, buffer.line_len(line)),
+        KeyCode::Left if control => buffer.word_left(line, column),
+        KeyCode::Right if control => buffer.word_right(line, column),
+        KeyCode::Left if column == 0 && line > 0 => (line - 1, buffer.line_len(line - 1)),
+        KeyCode::Left => (line, column.saturating_sub(1)),
+        KeyCode::Right if column >= buffer.line_len(line) && line < last => (line + 1, 0),
+        KeyCode::Right => (line, column.saturating_add(1)),
+        _ => return None,
+    };
+    Some((target.0, target.1.min(buffer.line_len(target.0))))
+}
+
+fn open_find(file: &mut FileScout, file_struct: &mut FileStruct) {
+    if file.mode == ViewMode::ContentView
+        && (file_struct.large.is_some()
+            || file_struct.binary.is_some()
+            || file_struct.content.is_empty())
+    {
+        file_struct.error = Some(Error::new(
+            ErrorKind::Unsupported,
+            "find is only available in text previews",
+        ));
+        return;
+    }
+    file.input.content = file.finder.query.to_string();
+    file.finder.origin = Some((file.mode, file.text_scroll_y, file.text_scroll_x));
+    file.mode = ViewMode::Find;
+}
+
+fn find_in_editor(
+    file: &mut FileScout,
+    from: (usize, usize),
+    forward: bool,
+    inclusive: bool,
+) -> bool {
+    let buffer = &file.input.buffer;
+    let offset = buffer.byte_of(from.0, from.1);
+    let found = file.finder.find(
+        buffer.line_count(),
+        |line| buffer.line(line),
+        (from.0, offset),
+        forward,
+        inclusive,
+    );
+    let Some((line, range)) = found else {
+        return false;
+    };
+    file.text_scroll_y = line;
+    file.text_scroll_x = buffer.column_of(line, range.start);
+    true
+}
+
+fn find_in_content(
+    file: &mut FileScout,
+    file_struct: &FileStruct,
+    from: (usize, usize),
+    forward: bool,
+    inclusive: bool,
+) -> bool {
+    let found = file.finder.find(
+        file_struct.lines.len(),
+        |line| {
+            file_struct
+                .content_line(line)
+                .unwrap_or_default()
+                .to_string()
+        },
+        from,
+        forward,
+        inclusive,
+    );
+    let found_any = found.is_some();
+    file.finder.current = found;
+    file.finder.reveal = true;
+    found_any
+}
+
+fn handle_find(
+    code: KeyCode,
+    file: &mut FileScout,
+    file_struct: MutexGuard<FileStruct>,
+    modifier: KeyModifiers,
+) {
+    let Some((mode, y, x)) = file.finder.origin else {
+        return;
+    };
+    match (code, modifier) {
+        (KeyCode::Char('r'), KeyModifiers::ALT) => file.finder.regex = !file.finder.regex,
+        (KeyCode::Char('c'), KeyModifiers::ALT) => {
+            file.finder.ignore_case = !file.finder.ignore_case
+        }
+        (KeyCode::Char(c), _) => file.input.content.push(c),
+        (KeyCode::Backspace, _) => {
+            file.input.content.pop();
+        }
+        (KeyCode::Enter, _) => {
+            if !file.finder.is_active() {
+                file.finder.clear();
+            }
+            file.input.content.clear();
+            file.finder.origin = None;
+            file.mode = mode;
+            return;
         }
+        (KeyCode::Esc, _) => {
+            file.finder.clear();
+            file.input.content.clear();
+            file.finder.origin = None;
+            file.text_scroll_y = y;
+            file.text_scroll_x = x;
+            file.mode = mode;
+            return;
+        }
+        _ => return,
+    }
+
+    file.finder.query = file.input.content.to_string();
+    let _ = file.finder.compile();
+    let found = if mode == ViewMode::FileEdit {
+        find_in_editor(file, (y, x), true, true)
+    } else {
+        find_in_content(file, &file_struct, (y, 0), true, true)
+    };
+    if !found {
+        file.text_scroll_y = y;
+        file.text_scroll_x = x;
     }
 }
 
-fn remove_at(file: &mut FileScout) {
-    let lines = file.input.content.lines();
-    if file.input.content.lines().count() != 0 {
-        let mut list: Vec<String> = lines.map(|ln| ln.to_string()).collect();
-        if list.len() > file.text_scroll_y {
-            if list[file.text_scroll_y].len() >= file.text_scroll_x {
-                list[file.text_scroll_y].remove(file.text_scroll_x.saturating_sub(1));
-                file.input.content = list.join("\n");
+fn handle_replace(code: KeyCode, file: &mut FileScout, modifier: KeyModifiers) {
+    let replacement = file.input.content.to_string();
+    match (code, modifier) {
+        (KeyCode::Char('a'), KeyModifiers::ALT) => {
+            let cursor = (file.text_scroll_y, file.text_scroll_x);
+            let finder = &file.finder;
+            let replaced = file
+                .input
+                .buffer
+                .replace_lines(cursor, |line| finder.replace_all(line, &replacement));
+            if replaced > 0 {
+                let buffer = &file.input.buffer;
+                file.text_scroll_y = file.text_scroll_y.min(buffer.line_count() - 1);
+                file.text_scroll_x = file.text_scroll_x.min(buffer.line_len(file.text_scroll_y));
             }
-        } else {
+            file.finder.replacement = replacement;
+            file.input.content.clear();
+            file.finder.origin = None;
+            file.mode = ViewMode::FileEdit;
+        }
+        (KeyCode::Char(c), _) => file.input.content.push(c),
+        (KeyCode::Backspace, _) => {
             file.input.content.pop();
-            file.text_scroll_y = file.text_scroll_y.saturating_sub(1)
         }
+        (KeyCode::Enter, _) => {
+            let buffer = &mut file.input.buffer;
+            let line = file.text_scroll_y;
+            let text = buffer.line(line);
+            let cursor = buffer.byte_of(line, file.text_scroll_x);
+            let replaced = file
+                .finder
+                .matches(&text)
+                .into_iter()
+                .find(|range| range.start == cursor)
+                .and_then(|range| {
+                    let expanded = file.finder.expand(&text, &range, &replacement)?;
+                    Some(buffer.replace_range(line, range, &expanded))
+                });
+            if let Some(cursor) = replaced {
+                (file.text_scroll_y, file.text_scroll_x) = cursor;
+            }
+            let from = (file.text_scroll_y, file.text_scroll_x);
+            find_in_editor(file, from, true, true);
+        }
+        (KeyCode::Esc, _) => {
+            file.finder.replacement = replacement;
+            file.input.content.clear();
+            file.finder.origin = None;
+            file.mode = ViewMode::FileEdit;
+        }
+        _ => {}
     }
 }
 
-fn handle_file_name(code: KeyCode, file: &mut FileScout, mut file_struct: MutexGuard<FileStruct>) {
+fn handle_file_name(
+    code: KeyCode,
+    file: &mut FileScout,
+    mut file_struct: MutexGuard<FileStruct>,
+    tx: Sender<String>,
+) {
     match code {
         KeyCode::Char(c) => file.input.content.push(c),
         KeyCode::Backspace => {
@@ -399,6 +1049,8 @@ fn handle_file_name(code: KeyCode, file: &mut FileScout, mut file_struct: MutexG
                 }
             } else if file.mode == ViewMode::Rename {
                 file_struct.rename(&file.input.content);
+            } else if file.mode == ViewMode::CreateArchive {
+                create_archive(file, &mut file_struct, tx);
             }
             reset_mode(file);
             let pwd = file_struct.pwd.to_path_buf();
@@ -411,6 +1063,451 @@ fn handle_file_name(code: KeyCode, file: &mut FileScout, mut file_struct: MutexG
     }
 }
 
+fn handle_search_input(
+    code: KeyCode,
+    file: &mut FileScout,
+    mut file_struct: MutexGuard<FileStruct>,
+    modifier: KeyModifiers,
+    tx: Sender<String>,
+) {
+    match (code, modifier) {
+        (KeyCode::Char('r'), KeyModifiers::ALT) => file.search.regex = !file.search.regex,
+        (KeyCode::Char('c'), KeyModifiers::ALT) => {
+            file.search.ignore_case = !file.search.ignore_case
+        }
+        (KeyCode::Char(c), _) => file.input.content.push(c),
+        (KeyCode::Backspace, _) => {
+            file.input.content.pop();
+        }
+        (KeyCode::Enter, _) => {
+            if file.input.content.is_empty() {
+                return;
+            }
+            let query = file.input.content.to_string();
+            let pwd = file_struct.pwd.to_path_buf();
+            match file.search.start(&pwd, &query, tx) {
+                Ok(()) => {
+                    file.input.content.clear();
+                    file.mode = ViewMode::SearchResults;
+                }
+                Err(error) => file_struct.error = Some(error),
+            }
+        }
+        (KeyCode::Esc, _) => reset_mode(file),
+        _ => {}
+    }
+}
+
+fn handle_search_results(
+    code: KeyCode,
+    file: &mut FileScout,
+    mut file_struct: MutexGuard<FileStruct>,
+) {
+    match code {
+        KeyCode::Down => file.search.state.select_next(),
+        KeyCode::Up => file.search.state.select_previous(),
+        KeyCode::Enter | KeyCode::Right => {
+            if let Some((path, line)) = file.search.selected_hit() {
+                file.search.cancel();
+                file_struct.reveal(&path);
+                file.mode = ViewMode::ContentView;
+                file.text_scroll_y = line.saturating_sub(1);
+                file.text_scroll_x = 0;
+            }
+        }
+        KeyCode::Esc | KeyCode::Left => {
+            file.search.cancel();
+            reset_mode(file);
+        }
+        _ => {}
+    }
+}
+
+fn handle_mark(code: KeyCode, file: &mut FileScout, mut file_struct: MutexGuard<FileStruct>) {
+    match code {
+        KeyCode::Char(key) if key.is_ascii_alphabetic() => {
+            if file.mode == ViewMode::MarkSet {
+                let pwd = file_struct.pwd.to_path_buf();
+                if let Err(error) = file.bookmarks.set(key, &pwd) {
+                    file_struct.error = Some(error);
+                }
+            } else if let Some(index) = file
+                .bookmarks
+                .entries
+                .iter()
+                .position(|bookmark| bookmark.key == key)
+            {
+                jump_to_bookmark(file, &mut file_struct, index);
+            } else {
+                file_struct.error = Some(Error::new(
+                    ErrorKind::NotFound,
+                    format!("no bookmark '{}'", key),
+                ));
+            }
+            reset_mode(file);
+        }
+        KeyCode::Esc => reset_mode(file),
+        _ => {}
+    }
+}
+
+fn handle_bookmarks(code: KeyCode, file: &mut FileScout, mut file_struct: MutexGuard<FileStruct>) {
+    match code {
+        KeyCode::Down => file.bookmarks.state.select_next(),
+        KeyCode::Up => file.bookmarks.state.select_previous(),
+        KeyCode::Enter | KeyCode::Right => {
+            if let Some(index) = file.bookmarks.state.selected() {
+                if jump_to_bookmark(file, &mut file_struct, index) {
+                    reset_mode(file);
+                }
+            }
+        }
+        KeyCode::Char('r') | KeyCode::Char('R') => {
+            if let Some(index) = file.bookmarks.state.selected() {
+                if let Some(bookmark) = file.bookmarks.entries.get(index) {
+                    file.input.content = bookmark.name.to_string();
+                    file.mode = ViewMode::BookmarkRename;
+                }
+            }
+        }
+        KeyCode::Delete | KeyCode::Char('d') | KeyCode::Char('D') => {
+            if let Some(index) = file.bookmarks.state.selected() {
+                if let Err(error) = file.bookmarks.remove(index) {
+                    file_struct.error = Some(error);
+                }
+            }
+        }
+        KeyCode::Esc | KeyCode::Char('b') | KeyCode::Char('B') | KeyCode::Char('q') => {
+            reset_mode(file)
+        }
+        _ => {}
+    }
+}
+
+fn handle_open_with(code: KeyCode, file: &mut FileScout, mut file_struct: MutexGuard<FileStruct>) {
+    match code {
+        KeyCode::Down => file.open_with.state.select_next(),
+        KeyCode::Up => file.open_with.state.select_previous(),
+        KeyCode::Enter => {
+            let open_with = &file.open_with;
+            let Some(launched) = open_with
+                .selected()
+                .map(|rule| rule.launch(&open_with.files))
+            else {
+                return;
+            };
+            file.mode = file.open_with.origin;
+            start_launched(file, &mut file_struct, launched);
+        }
+        KeyCode::Esc | KeyCode::Char('q') => file.mode = file.open_with.origin,
+        _ => {}
+    }
+}
+
+fn handle_shell(
+    code: KeyCode,
+    file: &mut FileScout,
+    mut file_struct: MutexGuard<FileStruct>,
+    tx: Sender<String>,
+) {
+    match code {
+        KeyCode::Char(c) => file.input.content.push(c),
+        KeyCode::Backspace => {
+            file.input.content.pop();
+        }
+        KeyCode::Enter => {
+            let template = file.input.content.trim();
+            let (template, background) = match template.strip_suffix('&') {
+                Some(template) if !template.ends_with('&') => (template.trim_end(), true),
+                _ => (template, false),
+            };
+            if template.is_empty() {
+                return reset_mode(file);
+            }
+            let selection = file_struct.selection();
+            if let Err(error) = archive::materialize(&selection) {
+                file_struct.error = Some(error);
+                return reset_mode(file);
+            }
+            let command = shell::expand(
+                template,
+                file_struct.current_path.as_deref(),
+                &selection,
+                &file_struct.pwd,
+            );
+            let pwd = file_struct.pwd.to_path_buf();
+            if background {
+                let files = Arc::clone(&file.files);
+                file.shell.spawn(command, &pwd, files, tx);
+            } else {
+                file.external = Some(ExternalCommand::shell(&command, &pwd));
+            }
+            reset_mode(file);
+        }
+        KeyCode::Esc => reset_mode(file),
+        _ => {}
+    }
+}
+
+fn handle_shell_log(code: KeyCode, file: &mut FileScout) {
+    let shell = &mut file.shell;
+    match code {
+        KeyCode::Up => shell.back = shell.back.saturating_add(1),
+        KeyCode::Down => shell.back = shell.back.saturating_sub(1),
+        KeyCode::PageUp => shell.back = shell.back.saturating_add(shell.page),
+        KeyCode::PageDown => shell.back = shell.back.saturating_sub(shell.page),
+        KeyCode::Home => shell.back = usize::MAX,
+        KeyCode::End => shell.back = 0,
+        KeyCode::Char('c') | KeyCode::Char('C') => shell.clear_finished(),
+        KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('j') | KeyCode::Char('J') => {
+            reset_mode(file)
+        }
+        _ => {}
+    }
+}
+
+fn handle_bookmark_rename(
+    code: KeyCode,
+    file: &mut FileScout,
+    mut file_struct: MutexGuard<FileStruct>,
+) {
+    match code {
+        KeyCode::Char(c) => file.input.content.push(c),
+        KeyCode::Backspace => {
+            file.input.content.pop();
+        }
+        KeyCode::Enter => {
+            if let Some(index) = file.bookmarks.state.selected() {
+                if let Err(error) = file.bookmarks.rename(index, &file.input.content) {
+                    file_struct.error = Some(error);
+                }
+            }
+            file.input.content.clear();
+            file.mode = ViewMode::Bookmarks;
+        }
+        KeyCode::Esc => {
+            file.input.content.clear();
+            file.mode = ViewMode::Bookmarks;
+        }
+        _ => {}
+    }
+}
+
+fn handle_jump(code: KeyCode, file: &mut FileScout, mut file_struct: MutexGuard<FileStruct>) {
+    match code {
+        KeyCode::Char(c) => {
+            file.input.content.push(c);
+            file.frecency.filter(&file.input.content);
+        }
+        KeyCode::Backspace => {
+            file.input.content.pop();
+            file.frecency.filter(&file.input.content);
+        }
+        KeyCode::Down => file.frecency.state.select_next(),
+        KeyCode::Up => file.frecency.state.select_previous(),
+        KeyCode::Enter => {
+            if let Some(path) = file.frecency.selected() {
+                let path = path.to_path_buf();
+                file_struct.present_dir_fn(&path, None);
+                reset_mode(file);
+            }
+        }
+        KeyCode::Esc => reset_mode(file),
+        _ => {}
+    }
+}
+
+fn handle_goto(code: KeyCode, file: &mut FileScout, mut file_struct: MutexGuard<FileStruct>) {
+    match code {
+        KeyCode::Char(c) => {
+            file.input.content.push(c);
+            file.path_prompt.candidates.clear();
+        }
+        KeyCode::Backspace => {
+            file.input.content.pop();
+            file.path_prompt.candidates.clear();
+        }
+        KeyCode::Tab => {
+            let pwd = file_struct.pwd.to_path_buf();
+            file.path_prompt.complete(&mut file.input.content, &pwd);
+        }
+        KeyCode::Enter => {
+            let path = PathPrompt::expand(&file.input.content, &file_struct.pwd);
+            if path.is_dir() {
+                file_struct.present_dir_fn(&path, None);
+            } else if path.exists() {
+                match (path.parent().map(fs::canonicalize), path.file_name()) {
+                    (Some(Ok(parent)), Some(name)) => file_struct.reveal(&parent.join(name)),
+                    (Some(Err(error)), _) => {
+                        file_struct.error = Some(error);
+                        return;
+                    }
+                    _ => return,
+                }
+            } else {
+                file_struct.error = Some(Error::new(
+                    ErrorKind::NotFound,
+                    format!("no such file or directory: {}", path.display()),
+                ));
+                return;
+            }
+            file.path_prompt.candidates.clear();
+            reset_mode(file);
+        }
+        KeyCode::Esc => {
+            file.path_prompt.candidates.clear();
+            reset_mode(file)
+        }
+        _ => {}
+    }
+}
+
+fn handle_goto_offset(
+    code: KeyCode,
+    file: &mut FileScout,
+    mut file_struct: MutexGuard<FileStruct>,
+) {
+    match code {
+        KeyCode::Char(c) if c.is_ascii_hexdigit() || c == 'x' || c == 'X' => {
+            file.input.content.push(c)
+        }
+        KeyCode::Backspace => {
+            file.input.content.pop();
+        }
+        KeyCode::Enter => {
+            let input = file.input.content.trim();
+            let parsed = match input
+                .strip_prefix("0x")
+                .or_else(|| input.strip_prefix("0X"))
+            {
+                Some(hex) => usize::from_str_radix(hex, 16),
+                None => input.parse::<usize>(),
+            };
+            match parsed {
+                Ok(value) => {
+                    let is_binary = file_struct.binary.is_some()
+                        || file_struct.large.as_ref().is_some_and(|large| large.binary);
+                    let row = if is_binary {
+                        value / HEX_WIDTH
+                    } else {
+                        value.saturating_sub(1)
+                    };
+                    file.text_scroll_y = row.min(file_struct.total_lines().saturating_sub(1));
+                    file.input.content.clear();
+                    file.mode = ViewMode::ContentView;
+                }
+                Err(error) => file_struct.error = Some(Error::new(ErrorKind::InvalidInput, error)),
+            }
+        }
+        KeyCode::Esc => {
+            file.input.content.clear();
+            file.mode = ViewMode::ContentView;
+        }
+        _ => {}
+    }
+}
+
+fn handle_disk_usage(code: KeyCode, file: &mut FileScout, mut file_struct: MutexGuard<FileStruct>) {
+    file_struct.error = None;
+    match code {
+        KeyCode::Down => file.disk_usage.state.select_next(),
+        KeyCode::Up => file.disk_usage.state.select_previous(),
+        KeyCode::Enter | KeyCode::Right => {
+            if let Some(entry) = file.disk_usage.selected().filter(|entry| entry.is_dir) {
+                let path = entry.path.to_path_buf();
+                file.disk_usage.open(&path);
+            }
+        }
+        KeyCode::Left => {
+            let root = file.disk_usage.root.to_path_buf();
+            if let Some(parent) = archive::entry_of(&root).parent() {
+                file.disk_usage.open(parent);
+                file.disk_usage.update(&file.sizes);
+                let entry = archive::entry_of(&root);
+                let index = file
+                    .disk_usage
+                    .entries
+                    .iter()
+                    .position(|usage| usage.path == entry);
+                file.disk_usage.state.select(index);
+            }
+        }
+        KeyCode::Delete => {
+            if archive::mount_of(&file.disk_usage.root).is_some() {
+                file_struct.error = Some(read_only());
+                return;
+            }
+            if let Some(entry) = file.disk_usage.selected() {
+                let path = entry.path.to_path_buf();
+                FileStruct::delete(&path, &mut file_struct);
+                file.sizes.forget(&path);
+                file.disk_usage.reload();
+            }
+        }
+        KeyCode::Char('r') | KeyCode::Char('R') => {
+            let root = file.disk_usage.root.to_path_buf();
+            file.sizes.rescan(&root);
+            file.disk_usage.reload();
+        }
+        KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('u') | KeyCode::Char('U') => {
+            file_struct.refresh();
+            reset_mode(file);
+        }
+        _ => {}
+    }
+}
+
+fn handle_duplicates(code: KeyCode, file: &mut FileScout, mut file_struct: MutexGuard<FileStruct>) {
+    file_struct.error = None;
+    match code {
+        KeyCode::Down => file.duplicates.state.select_next(),
+        KeyCode::Up => file.duplicates.state.select_previous(),
+        KeyCode::Char(' ') => file.duplicates.toggle_mark(),
+        KeyCode::Char('a') | KeyCode::Char('A') => file.duplicates.mark_extras(),
+        KeyCode::Delete | KeyCode::Char('h') | KeyCode::Char('H') => {
+            let result = if archive::mount_of(&file.duplicates.root).is_some() {
+                Err(read_only())
+            } else if code == KeyCode::Delete {
+                file.duplicates.trash_marked()
+            } else {
+                file.duplicates.link_marked()
+            };
+            if let Err(error) = result {
+                file_struct.error = Some(error);
+            }
+        }
+        KeyCode::Enter | KeyCode::Right => {
+            if let Some(path) = file.duplicates.selected_file() {
+                file.duplicates.cancel();
+                file_struct.reveal(&path);
+                reset_mode(file);
+            }
+        }
+        KeyCode::Esc | KeyCode::Left | KeyCode::Char('q') => {
+            file.duplicates.cancel();
+            file_struct.refresh();
+            reset_mode(file);
+        }
+        _ => {}
+    }
+}
+
+fn jump_to_bookmark(file: &FileScout, file_struct: &mut FileStruct, index: usize) -> bool {
+    let Some(bookmark) = file.bookmarks.entries.get(index) else {
+        return false;
+    };
+    if bookmark.is_broken() {
+        file_struct.error = Some(Error::new(
+            ErrorKind::NotFound,
+            format!("bookmark '{}' points to a missing path", bookmark.key),
+        ));
+        return false;
+    }
+    let path = bookmark.path.to_path_buf();
+    file_struct.present_dir_fn(&path, None);
+    true
+}
+
 fn reset_mode(file: &mut FileScout) {
     file.input.content.clear();
     file.mode = ViewMode::ListView;
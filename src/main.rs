@@ -1,8 +1,28 @@
+mod archive;
+mod bookmarks;
+mod clipboard;
 mod constant;
 mod crypto_handler;
+mod disk_usage;
+mod duplicates;
+mod encoding;
 mod explorer;
+mod external;
+mod find;
+mod highlight;
+mod history;
+mod image_preview;
 mod key_events;
+mod mime;
+mod open_with;
+mod path_prompt;
+mod preview;
+mod search;
+mod shell;
+mod tabs;
+mod text_buffer;
 mod ui;
+mod watcher;
 
 use color_eyre::eyre::Result;
 use explorer::FileStruct;
@@ -17,7 +37,9 @@ async fn main() -> Result<()> {
     file.present_dir_fn(Path::new("."), None);
     let mut terminal: DefaultTerminal = ratatui::init();
     let app = FileScout::new(file);
-    app.run(&mut terminal).await?;
+    let result = app.run(&mut terminal).await;
     ratatui::restore();
+    archive::clear_mounts();
+    result?;
     Ok(())
 }
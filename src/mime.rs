@@ -0,0 +1,167 @@
+use std::{fs::File, io::Read, path::Path};
+
+use crate::explorer::looks_binary;
+
+const SNIFF_LEN: u64 = 8192;
+
+const MAGIC: [(usize, &[u8], &str); 23] = [
+    (0, b"%PDF-", "application/pdf"),
+    (0, b"\x89PNG\r\n\x1a\n", "image/png"),
+    (0, b"\xff\xd8\xff", "image/jpeg"),
+    (0, b"GIF87a", "image/gif"),
+    (0, b"GIF89a", "image/gif"),
+    (8, b"WEBP", "image/webp"),
+    (4, b"ftypheic", "image/heic"),
+    (4, b"ftypM4A ", "audio/mp4"),
+    (4, b"ftypqt  ", "video/quicktime"),
+    (4, b"ftyp", "video/mp4"),
+    (8, b"AVI ", "video/x-msvideo"),
+    (8, b"WAVE", "audio/wav"),
+    (0, b"\x1a\x45\xdf\xa3", "video/x-matroska"),
+    (0, b"ID3", "audio/mpeg"),
+    (0, b"fLaC", "audio/flac"),
+    (0, b"OggS", "audio/ogg"),
+    (0, b"PK\x03\x04", "application/zip"),
+    (0, b"\x1f\x8b", "application/gzip"),
+    (0, b"7z\xbc\xaf\x27\x1c", "application/x-7z-compressed"),
+    (0, b"\x28\xb5\x2f\xfd", "application/zstd"),
+    (257, b"ustar", "application/x-tar"),
+    (0, b"\x7fELF", "application/x-executable"),
+    (0, b"\x00\x00\x01\x00", "image/vnd.microsoft.icon"),
+];
+
+const EXTENSIONS: [(&str, &str); 44] = [
+    ("pdf", "application/pdf"),
+    ("png", "image/png"),
+    ("jpg", "image/jpeg"),
+    ("jpeg", "image/jpeg"),
+    ("gif", "image/gif"),
+    ("webp", "image/webp"),
+    ("bmp", "image/bmp"),
+    ("svg", "image/svg+xml"),
+    ("ico", "image/vnd.microsoft.icon"),
+    ("heic", "image/heic"),
+    ("mp4", "video/mp4"),
+    ("m4v", "video/mp4"),
+    ("mkv", "video/x-matroska"),
+    ("webm", "video/webm"),
+    ("mov", "video/quicktime"),
+    ("avi", "video/x-msvideo"),
+    ("mp3", "audio/mpeg"),
+    ("m4a", "audio/mp4"),
+    ("flac", "audio/flac"),
+    ("ogg", "audio/ogg"),
+    ("opus", "audio/ogg"),
+    ("wav", "audio/wav"),
+    ("zip", "application/zip"),
+    ("gz", "application/gzip"),
+    ("tgz", "application/gzip"),
+    ("7z", "application/x-7z-compressed"),
+    ("zst", "application/zstd"),
+    ("tar", "application/x-tar"),
+    ("epub", "application/epub+zip"),
+    (
+        "docx",
+        "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+    ),
+    (
+        "xlsx",
+        "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+    ),
+    (
+        "pptx",
+        "application/vnd.openxmlformats-officedocument.presentationml.presentation",
+    ),
+    ("odt", "application/vnd.oasis.opendocument.text"),
+    ("ods", "application/vnd.oasis.opendocument.spreadsheet"),
+    ("html", "text/html"),
+    ("htm", "text/html"),
+    ("css", "text/css"),
+    ("csv", "text/csv"),
+    ("md", "text/markdown"),
+    ("json", "application/json"),
+    ("xml", "application/xml"),
+    ("js", "text/javascript"),
+    ("sh", "text/x-shellscript"),
+    ("rs", "text/x-rust"),
+];
+
+fn by_magic(bytes: &[u8]) -> Option<&'static str> {
+    MAGIC
+        .iter()
+        .find(|(offset, magic, _)| bytes.get(*offset..*offset + magic.len()) == Some(*magic))
+        .map(|(.., mime)| *mime)
+}
+
+fn by_extension(path: &Path) -> Option<&'static str> {
+    let extension = path.extension()?.to_string_lossy().to_lowercase();
+    EXTENSIONS
+        .iter()
+        .find(|(known, _)| *known == extension)
+        .map(|(_, mime)| *mime)
+}
+
+pub fn detect(path: &Path) -> String {
+    if path.is_dir() {
+        return String::from("inode/directory");
+    }
+    let mut bytes = Vec::new();
+    if let Ok(file) = File::open(path) {
+        let _ = file.take(SNIFF_LEN).read_to_end(&mut bytes);
+    }
+    from_head(path, &bytes)
+}
+
+fn from_head(path: &Path, bytes: &[u8]) -> String {
+    // Zip containers (epub, docx, ...) are told apart by extension only.
+    let mime = match by_magic(bytes) {
+        Some("application/zip") | None => by_extension(path).or(by_magic(bytes)),
+        magic => magic,
+    };
+    match mime {
+        Some(mime) => mime.to_string(),
+        None if bytes.is_empty() || !looks_binary(bytes) => String::from("text/plain"),
+        None => String::from("application/octet-stream"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detect_prefers_magic_over_extension() {
+        let cases: [(&str, &[u8], &str); 9] = [
+            ("photo.jpg", b"\x89PNG\r\n\x1a\n", "image/png"),
+            ("notes.txt", b"%PDF-1.7", "application/pdf"),
+            ("clip.MP4", b"\0\0\0\x20ftypisom", "video/mp4"),
+            ("song.m4a", b"\0\0\0\x20ftypM4A ", "audio/mp4"),
+            ("page.HTML", b"<!doctype html>", "text/html"),
+            ("empty.rs", b"", "text/x-rust"),
+            ("README", b"plain words\n", "text/plain"),
+            ("empty", b"", "text/plain"),
+            ("blob", b"\0\x01\x02\x03", "application/octet-stream"),
+        ];
+        for (name, bytes, mime) in cases {
+            assert_eq!(from_head(Path::new(name), bytes), mime, "{}", name);
+        }
+    }
+
+    #[test]
+    fn detect_tells_zip_containers_apart_by_extension() {
+        let zip = b"PK\x03\x04\x14\0";
+        let cases = [
+            ("book.epub", "application/epub+zip"),
+            (
+                "report.docx",
+                "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+            ),
+            ("archive.zip", "application/zip"),
+            ("archive.jar", "application/zip"),
+            ("archive", "application/zip"),
+        ];
+        for (name, mime) in cases {
+            assert_eq!(from_head(Path::new(name), zip), mime, "{}", name);
+        }
+    }
+}
@@ -0,0 +1,234 @@
+use std::{
+    env, fs,
+    io::{Error, ErrorKind, Result},
+    path::{Path, PathBuf},
+};
+
+use globset::{GlobBuilder, GlobMatcher};
+use ratatui::widgets::ListState;
+
+use crate::{
+    constant::config_file,
+    external::{split_words, ExternalCommand},
+    mime,
+    ui::ViewMode,
+};
+
+pub const RULES_FILE: &str = "open.conf";
+
+#[cfg(target_os = "macos")]
+const DEFAULT_RULES: &str = "text/* term $VISUAL\ntext/* term $EDITOR\ntext/* term vi\n* gui open";
+#[cfg(not(target_os = "macos"))]
+const DEFAULT_RULES: &str =
+    "text/* term $VISUAL\ntext/* term $EDITOR\ntext/* term vi\n* gui xdg-open";
+
+enum Pattern {
+    Mime(String),
+    Glob(GlobMatcher),
+}
+
+impl Pattern {
+    fn parse(pattern: &str) -> Option<Self> {
+        if pattern.contains('/') {
+            return Some(Pattern::Mime(pattern.to_lowercase()));
+        }
+        let glob = GlobBuilder::new(pattern)
+            .case_insensitive(true)
+            .build()
+            .ok()?;
+        Some(Pattern::Glob(glob.compile_matcher()))
+    }
+
+    fn matches(&self, path: &Path, mime: &str) -> bool {
+        match self {
+            Pattern::Mime(pattern) => match pattern.strip_suffix("/*") {
+                Some("*") => true,
+                Some(kind) => mime.split('/').next() == Some(kind),
+                None => pattern == mime,
+            },
+            Pattern::Glob(matcher) => path.file_name().is_some_and(|name| matcher.is_match(name)),
+        }
+    }
+}
+
+pub struct Rule {
+    pub pattern: String,
+    pub terminal: bool,
+    pub command: Vec<String>,
+    matcher: Pattern,
+}
+
+impl Rule {
+    /// Parses `pattern gui|term command...`, skipping rules whose `$VAR` is unset.
+    fn parse(line: &str) -> Option<Self> {
+        let words = split_words(line)?;
+        let mut words = words.iter();
+        let pattern = words.next()?;
+        let terminal = match words.next()?.as_str() {
+            "term" => true,
+            "gui" => false,
+            _ => return None,
+        };
+        let mut command = Vec::new();
+        for word in words {
+            match word.strip_prefix('$') {
+                Some(variable) => {
+                    let value = env::var(variable).ok()?;
+                    command.extend(split_words(&value)?);
+                }
+                None => command.push(word.to_string()),
+            }
+        }
+        if command.is_empty() {
+            return None;
+        }
+        Some(Rule {
+            pattern: pattern.to_string(),
+            terminal,
+            command,
+            matcher: Pattern::parse(pattern)?,
+        })
+    }
+
+    /// Starts GUI rules detached and hands terminal rules back to run in the foreground.
+    pub fn launch(&self, files: &[PathBuf]) -> Result<Option<ExternalCommand>> {
+        let command = ExternalCommand::from_template(&self.command, files)?;
+        if self.terminal {
+            return Ok(Some(command));
+        }
+        command.spawn_detached()?;
+        Ok(None)
+    }
+}
+
+pub struct OpenWith {
+    pub rules: Vec<Rule>,
+    pub choices: Vec<usize>,
+    pub files: Vec<PathBuf>,
+    pub origin: ViewMode,
+    pub state: ListState,
+}
+
+impl OpenWith {
+    pub fn load() -> Self {
+        let content = config_file(RULES_FILE)
+            .and_then(|path| fs::read_to_string(path).ok())
+            .unwrap_or_else(|| DEFAULT_RULES.to_string());
+        let rules = content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(Rule::parse)
+            .collect();
+        OpenWith {
+            rules,
+            choices: Vec::new(),
+            files: Vec::new(),
+            origin: ViewMode::ListView,
+            state: ListState::default(),
+        }
+    }
+
+    /// Indices of the rules that match every one of `files`, in config order.
+    pub fn matching(&self, files: &[PathBuf]) -> Vec<usize> {
+        let mimes: Vec<String> = files.iter().map(|file| mime::detect(file)).collect();
+        (0..self.rules.len())
+            .filter(|index| {
+                files
+                    .iter()
+                    .zip(&mimes)
+                    .all(|(file, mime)| self.rules[*index].matcher.matches(file, mime))
+            })
+            .collect()
+    }
+
+    pub fn default_rule(&self, files: &[PathBuf]) -> Result<&Rule> {
+        self.matching(files)
+            .first()
+            .map(|index| &self.rules[*index])
+            .ok_or_else(|| no_rule(files))
+    }
+
+    pub fn prepare(&mut self, files: Vec<PathBuf>, origin: ViewMode) -> Result<()> {
+        self.choices = self.matching(&files);
+        if self.choices.is_empty() {
+            return Err(no_rule(&files));
+        }
+        self.files = files;
+        self.origin = origin;
+        self.state.select_first();
+        Ok(())
+    }
+
+    pub fn selected(&self) -> Option<&Rule> {
+        let index = self.choices.get(self.state.selected()?)?;
+        self.rules.get(*index)
+    }
+}
+
+fn no_rule(files: &[PathBuf]) -> Error {
+    let mime = files
+        .first()
+        .map(|file| mime::detect(file))
+        .unwrap_or_default();
+    Error::new(
+        ErrorKind::NotFound,
+        format!("no open rule for {}, add one to {}", mime, RULES_FILE),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_words_keeps_quoted_runs() {
+        let cases: [(&str, Option<&[&str]>); 7] = [
+            ("", Some(&[])),
+            ("  vi  -R ", Some(&["vi", "-R"])),
+            ("code --wait", Some(&["code", "--wait"])),
+            ("\"my editor\" -x", Some(&["my editor", "-x"])),
+            ("a'b c'd", Some(&["ab cd"])),
+            ("say '' done", Some(&["say", "", "done"])),
+            ("vim \"unbalanced", None),
+        ];
+        for (line, words) in cases {
+            let words = words.map(|words| words.iter().map(|word| word.to_string()).collect());
+            assert_eq!(split_words(line), words, "{:?}", line);
+        }
+    }
+
+    #[test]
+    fn rule_expands_variables() {
+        env::set_var("OPEN_WITH_TEST_EDITOR", "nvim -R");
+        let rule = Rule::parse("text/* term $OPEN_WITH_TEST_EDITOR +1").unwrap();
+        assert!(rule.terminal);
+        assert_eq!(rule.command, ["nvim", "-R", "+1"]);
+
+        env::remove_var("OPEN_WITH_TEST_UNSET");
+        assert!(Rule::parse("text/* term $OPEN_WITH_TEST_UNSET").is_none());
+        assert!(Rule::parse("* window xdg-open").is_none());
+        assert!(Rule::parse("* gui").is_none());
+    }
+
+    #[test]
+    fn pattern_matches_mime_and_glob() {
+        let cases = [
+            ("text/*", "notes.md", "text/markdown", true),
+            ("text/*", "photo.png", "image/png", false),
+            ("*/*", "photo.png", "image/png", true),
+            ("image/png", "photo.png", "image/png", true),
+            ("IMAGE/PNG", "photo.png", "image/png", true),
+            ("image/png", "photo.gif", "image/gif", false),
+            ("*.png", "dir/PHOTO.PNG", "image/png", true),
+            ("*.png", "photo.png.bak", "text/plain", false),
+            ("Makefile", "src/Makefile", "text/plain", true),
+        ];
+        for (pattern, path, mime, matches) in cases {
+            let matched = Pattern::parse(pattern)
+                .unwrap()
+                .matches(Path::new(path), mime);
+            assert_eq!(matched, matches, "{} {}", pattern, path);
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use std::{
+    fs,
+    path::{Path, PathBuf, MAIN_SEPARATOR},
+};
+
+#[derive(Default)]
+pub struct PathPrompt {
+    pub candidates: Vec<String>,
+}
+
+impl PathPrompt {
+    pub fn expand(input: &str, pwd: &Path) -> PathBuf {
+        let path = if input == "~" {
+            dirs::home_dir().unwrap_or_default()
+        } else if let Some(rest) = input.strip_prefix("~/") {
+            dirs::home_dir().unwrap_or_default().join(rest)
+        } else {
+            PathBuf::from(input)
+        };
+        if path.is_absolute() {
+            path
+        } else {
+            pwd.join(path)
+        }
+    }
+
+    pub fn complete(&mut self, input: &mut String, pwd: &Path) {
+        let (dir_part, prefix) = match input.rfind(MAIN_SEPARATOR) {
+            Some(index) => input.split_at(index + 1),
+            None => ("", input.as_str()),
+        };
+        let dir = if dir_part.is_empty() {
+            pwd.to_path_buf()
+        } else {
+            PathPrompt::expand(dir_part, pwd)
+        };
+
+        let mut names: Vec<String> = fs::read_dir(&dir)
+            .into_iter()
+            .flatten()
+            .filter_map(Result::ok)
+            .filter(|entry| entry.path().is_dir())
+            .filter_map(|entry| entry.file_name().to_str().map(str::to_string))
+            .filter(|name| name.starts_with(prefix))
+            .filter(|name| prefix.starts_with('.') || !name.starts_with('.'))
+            .collect();
+        names.sort();
+
+        let completed = match names.as_slice() {
+            [] => None,
+            [name] => Some(format!("{}{}{}", dir_part, name, MAIN_SEPARATOR)),
+            [first, rest @ ..] => {
+                let common = rest.iter().fold(first.as_str(), |common, name| {
+                    let len = common
+                        .char_indices()
+                        .zip(name.chars())
+                        .take_while(|((_, a), b)| a == b)
+                        .last()
+                        .map(|((index, ch), _)| index + ch.len_utf8())
+                        .unwrap_or(0);
+                    &common[..len]
+                });
+                Some(format!("{}{}", dir_part, common))
+            }
+        };
+        if let Some(completed) = completed {
+            *input = completed;
+        }
+        self.candidates = if names.len() > 1 { names } else { Vec::new() };
+    }
+}
@@ -0,0 +1,283 @@
+use std::{
+    fs::{self, File},
+    io::{Read, Result, Seek, SeekFrom},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use encoding_rs::{UTF_16BE, UTF_16LE, UTF_8};
+
+use crate::{
+    encoding::{TextFormat, SNIFF_LEN},
+    explorer::HEX_WIDTH,
+};
+
+pub const LARGE_FILE: u64 = 2 * 1024 * 1024;
+const CHUNK: usize = 1024 * 1024;
+const LOOKAHEAD: usize = 200;
+const MAX_LINE_BYTES: u64 = 16 * 1024;
+const MAX_WINDOW_BYTES: u64 = 1024 * 1024;
+const POLL: Duration = Duration::from_millis(250);
+
+/// Where the text starts after a byte order mark, and the code unit ending a line.
+#[derive(Clone, Copy)]
+struct Layout {
+    start: u64,
+    newline: &'static [u8],
+}
+
+impl Layout {
+    fn of(format: &TextFormat) -> Self {
+        let (bom, newline): (u64, &[u8]) = if format.encoding == UTF_16LE {
+            (2, b"\n\0")
+        } else if format.encoding == UTF_16BE {
+            (2, b"\0\n")
+        } else {
+            (3, b"\n")
+        };
+        Self {
+            start: if format.bom { bom } else { 0 },
+            newline,
+        }
+    }
+}
+
+#[derive(Default)]
+struct LineIndex {
+    offsets: Vec<u64>,
+    indexed: u64,
+    complete: bool,
+}
+
+impl LineIndex {
+    fn new(start: u64) -> Self {
+        Self {
+            offsets: vec![start],
+            indexed: start,
+            complete: false,
+        }
+    }
+
+    fn line_count(&self) -> usize {
+        match self.offsets.last() {
+            Some(&last) if last >= self.indexed => self.offsets.len() - 1,
+            _ => self.offsets.len(),
+        }
+    }
+
+    fn span(&self, line: usize) -> Option<(u64, u64)> {
+        let start = *self.offsets.get(line)?;
+        let end = self.offsets.get(line + 1).copied().unwrap_or(self.indexed);
+        (start < end).then_some((start, end))
+    }
+}
+
+pub struct LargeFile {
+    pub path: PathBuf,
+    pub binary: bool,
+    pub format: Option<TextFormat>,
+    pub follow: bool,
+    index: Arc<Mutex<LineIndex>>,
+    window_start: usize,
+    window: Vec<String>,
+    cancel: Arc<AtomicBool>,
+}
+
+impl LargeFile {
+    pub fn open(path: &Path) -> Result<Self> {
+        // Only the head is sniffed, bytes past it that don't decode show up as U+FFFD.
+        let mut head = vec![0; SNIFF_LEN];
+        let read = read_full(&mut File::open(path)?, &mut head)?;
+        head.truncate(read);
+        let format = TextFormat::decode(&head)
+            .map(|(_, format)| format)
+            .filter(|format| {
+                let encoding = format.encoding;
+                encoding.is_ascii_compatible() || encoding == UTF_16LE || encoding == UTF_16BE
+            });
+        let binary = format.is_none();
+
+        let index = Arc::new(Mutex::new(LineIndex::default()));
+        let cancel = Arc::new(AtomicBool::new(false));
+        if let Some(format) = &format {
+            let layout = Layout::of(format);
+            *index.lock().unwrap() = LineIndex::new(layout.start);
+            let path = path.to_path_buf();
+            let index = Arc::clone(&index);
+            let cancel = Arc::clone(&cancel);
+            thread::spawn(move || build_index(&path, layout, &index, &cancel));
+        }
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            binary,
+            format,
+            follow: false,
+            index,
+            window_start: 0,
+            window: Vec::new(),
+            cancel,
+        })
+    }
+
+    pub fn size(&self) -> u64 {
+        fs::metadata(&self.path)
+            .map(|metadata| metadata.len())
+            .unwrap_or_default()
+    }
+
+    pub fn line_count(&self) -> usize {
+        if self.binary {
+            return (self.size() as usize).div_ceil(HEX_WIDTH);
+        }
+        self.index.lock().unwrap().line_count()
+    }
+
+    pub fn is_streaming(&self) -> bool {
+        self.follow || (!self.binary && !self.index.lock().unwrap().complete)
+    }
+
+    pub fn lines(&mut self, start: usize, count: usize) -> &[String] {
+        let cached =
+            start >= self.window_start && start + count <= self.window_start + self.window.len();
+        if !cached || self.follow {
+            self.window = self
+                .read_lines(start, count + LOOKAHEAD)
+                .unwrap_or_default();
+            self.window_start = start;
+        }
+        let from = (start - self.window_start).min(self.window.len());
+        let to = (from + count).min(self.window.len());
+        &self.window[from..to]
+    }
+
+    pub fn bytes(&self, first_row: usize, rows: usize) -> Vec<u8> {
+        let mut buf = vec![0; rows * HEX_WIDTH];
+        let read = File::open(&self.path).and_then(|mut file| {
+            file.seek(SeekFrom::Start((first_row * HEX_WIDTH) as u64))?;
+            read_full(&mut file, &mut buf)
+        });
+        buf.truncate(read.unwrap_or_default());
+        buf
+    }
+
+    fn read_lines(&self, start: usize, count: usize) -> Result<Vec<String>> {
+        let spans: Vec<(u64, u64)> = {
+            let index = self.index.lock().unwrap();
+            (start..start + count)
+                .map_while(|line| index.span(line))
+                .collect()
+        };
+        let (Some(first), Some(last)) = (spans.first(), spans.last()) else {
+            return Ok(Vec::new());
+        };
+        let mut file = File::open(&self.path)?;
+        let encoding = self.format.map(|format| format.encoding).unwrap_or(UTF_8);
+        let decode = |bytes: &[u8], truncated: bool| {
+            let line = encoding.decode_without_bom_handling(bytes).0;
+            let line = line.trim_end_matches(['\n', '\r']);
+            match truncated {
+                true => format!("{}…", line),
+                false => line.to_string(),
+            }
+        };
+
+        // Small windows are read in one go, otherwise each line is read up to a cap so a
+        // huge line (or a file without newlines) doesn't pull the whole file into memory.
+        if last.1 - first.0 <= MAX_WINDOW_BYTES {
+            let mut buf = vec![0; (last.1 - first.0) as usize];
+            file.seek(SeekFrom::Start(first.0))?;
+            let read = read_full(&mut file, &mut buf)?;
+            buf.truncate(read);
+            return Ok(spans
+                .iter()
+                .map(|(line_start, line_end)| {
+                    let from = ((line_start - first.0) as usize).min(buf.len());
+                    let to = ((line_end - first.0) as usize).min(buf.len());
+                    decode(&buf[from..to], false)
+                })
+                .collect());
+        }
+
+        let mut buf = vec![0; MAX_LINE_BYTES as usize];
+        spans
+            .iter()
+            .map(|(line_start, line_end)| {
+                let len = (line_end - line_start).min(MAX_LINE_BYTES) as usize;
+                file.seek(SeekFrom::Start(*line_start))?;
+                let read = read_full(&mut file, &mut buf[..len])?;
+                Ok(decode(&buf[..read], line_end - line_start > MAX_LINE_BYTES))
+            })
+            .collect()
+    }
+}
+
+impl Drop for LargeFile {
+    fn drop(&mut self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
+
+fn read_full(file: &mut File, buf: &mut [u8]) -> Result<usize> {
+    let mut total = 0;
+    while total < buf.len() {
+        match file.read(&mut buf[total..])? {
+            0 => break,
+            read => total += read,
+        }
+    }
+    Ok(total)
+}
+
+fn build_index(path: &Path, layout: Layout, index: &Mutex<LineIndex>, cancel: &AtomicBool) {
+    let unit = layout.newline.len();
+    let mut buf = vec![0; CHUNK];
+    while !cancel.load(Ordering::Relaxed) {
+        let indexed = index.lock().unwrap().indexed;
+        let len = fs::metadata(path)
+            .map(|metadata| metadata.len())
+            .unwrap_or_default();
+        if len < indexed {
+            *index.lock().unwrap() = LineIndex::new(layout.start.min(len));
+            continue;
+        }
+        // Whole code units only, a trailing half unit waits for the rest to be written.
+        let available = (len - indexed) / unit as u64 * unit as u64;
+        if available == 0 {
+            index.lock().unwrap().complete = true;
+            thread::sleep(POLL);
+            continue;
+        }
+
+        let Ok(mut file) = File::open(path) else {
+            return;
+        };
+        if file.seek(SeekFrom::Start(indexed)).is_err() {
+            return;
+        }
+        let want = available.min(CHUNK as u64) as usize;
+        let read = match read_full(&mut file, &mut buf[..want]) {
+            Ok(read) if read >= unit => read / unit * unit,
+            Ok(_) => {
+                thread::sleep(POLL);
+                continue;
+            }
+            Err(_) => return,
+        };
+        let mut index = index.lock().unwrap();
+        for (position, _) in buf[..read]
+            .chunks_exact(unit)
+            .enumerate()
+            .filter(|(_, code)| *code == layout.newline)
+        {
+            index.offsets.push(indexed + ((position + 1) * unit) as u64);
+        }
+        index.indexed = indexed + read as u64;
+        index.complete = len - index.indexed < unit as u64;
+    }
+}
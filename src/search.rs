@@ -0,0 +1,152 @@
+use std::{
+    fs::File,
+    io::{Error, ErrorKind, Read, Result},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+};
+
+use ignore::{WalkBuilder, WalkState};
+use ratatui::widgets::ListState;
+use regex::{Regex, RegexBuilder};
+use tokio::sync::mpsc::Sender;
+
+use crate::{
+    archive,
+    explorer::{looks_binary, SNIFF_LEN},
+};
+
+const MAX_HITS: usize = 10_000;
+const MAX_SNIPPET: usize = 200;
+
+pub struct SearchHit {
+    pub path: PathBuf,
+    pub line: usize,
+    pub snippet: String,
+}
+
+#[derive(Default)]
+pub struct SearchResults {
+    pub hits: Vec<SearchHit>,
+    pub running: bool,
+    pub truncated: bool,
+}
+
+#[derive(Default)]
+pub struct ContentSearch {
+    pub query: String,
+    pub regex: bool,
+    pub ignore_case: bool,
+    pub results: Arc<Mutex<SearchResults>>,
+    pub state: ListState,
+    cancel: Arc<AtomicBool>,
+}
+
+impl ContentSearch {
+    pub fn start(&mut self, root: &Path, query: &str, tx: Sender<String>) -> Result<()> {
+        let pattern = if self.regex {
+            query.to_string()
+        } else {
+            regex::escape(query)
+        };
+        let matcher = RegexBuilder::new(&pattern)
+            .case_insensitive(self.ignore_case)
+            .build()
+            .map_err(|error| Error::new(ErrorKind::InvalidInput, error))?;
+
+        self.cancel.store(true, Ordering::Relaxed);
+        self.cancel = Arc::new(AtomicBool::new(false));
+        self.results = Arc::new(Mutex::new(SearchResults {
+            running: true,
+            ..Default::default()
+        }));
+        self.query = query.to_string();
+        self.state = ListState::default().with_selected(Some(0));
+
+        let root = root.to_path_buf();
+        let results = Arc::clone(&self.results);
+        let cancel = Arc::clone(&self.cancel);
+        thread::spawn(move || {
+            // Unreadable entries are skipped below like any other unreadable file.
+            let _ = archive::materialize(&[root.to_path_buf()]);
+            WalkBuilder::new(&root).build_parallel().run(|| {
+                let matcher = matcher.clone();
+                let results = Arc::clone(&results);
+                let cancel = Arc::clone(&cancel);
+                let tx = tx.clone();
+                Box::new(move |entry| {
+                    if cancel.load(Ordering::Relaxed) {
+                        return WalkState::Quit;
+                    }
+                    let Ok(entry) = entry else {
+                        return WalkState::Continue;
+                    };
+                    if !entry.file_type().is_some_and(|kind| kind.is_file()) {
+                        return WalkState::Continue;
+                    }
+                    let hits = search_file(entry.path(), &matcher);
+                    if hits.is_empty() {
+                        return WalkState::Continue;
+                    }
+                    let mut results = results.lock().unwrap();
+                    let room = MAX_HITS.saturating_sub(results.hits.len());
+                    if hits.len() > room {
+                        results.truncated = true;
+                    }
+                    results.hits.extend(hits.into_iter().take(room));
+                    let _ = tx.try_send(String::new());
+                    if results.truncated {
+                        WalkState::Quit
+                    } else {
+                        WalkState::Continue
+                    }
+                })
+            });
+            results.lock().unwrap().running = false;
+            let _ = tx.try_send(String::from("Search completed"));
+        });
+        Ok(())
+    }
+
+    pub fn cancel(&self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+
+    pub fn selected_hit(&self) -> Option<(PathBuf, usize)> {
+        let index = self.state.selected()?;
+        let results = self.results.lock().unwrap();
+        let hit = results.hits.get(index)?;
+        Some((hit.path.to_path_buf(), hit.line))
+    }
+}
+
+fn search_file(path: &Path, matcher: &Regex) -> Vec<SearchHit> {
+    let Ok(mut file) = File::open(path) else {
+        return Vec::new();
+    };
+    // Binary files are skipped on their head, without reading the rest.
+    let mut bytes = Vec::new();
+    if (&mut file)
+        .take(SNIFF_LEN as u64)
+        .read_to_end(&mut bytes)
+        .is_err()
+        || looks_binary(&bytes)
+        || file.read_to_end(&mut bytes).is_err()
+    {
+        return Vec::new();
+    }
+    let content = String::from_utf8_lossy(&bytes);
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| matcher.is_match(line))
+        .map(|(index, line)| SearchHit {
+            path: path.to_path_buf(),
+            line: index + 1,
+            snippet: line.trim().chars().take(MAX_SNIPPET).collect(),
+        })
+        .collect()
+}
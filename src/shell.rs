@@ -0,0 +1,235 @@
+use std::{
+    collections::VecDeque,
+    ffi::{OsStr, OsString},
+    io::Error,
+    path::{Path, PathBuf},
+    process::Stdio,
+    sync::{Arc, Mutex},
+};
+
+use tokio::{
+    io::{AsyncBufReadExt, BufReader},
+    process::Command,
+    sync::mpsc::Sender,
+};
+
+use crate::explorer::FileStruct;
+
+const MAX_JOB_LINES: usize = 5000;
+
+pub struct Job {
+    pub id: usize,
+    pub command: String,
+    pub output: VecDeque<String>,
+    pub status: Option<Result<(), String>>,
+}
+
+#[derive(Default)]
+pub struct ShellLog {
+    pub jobs: Arc<Mutex<Vec<Job>>>,
+    pub back: usize,
+    pub page: usize,
+    next_id: usize,
+}
+
+impl ShellLog {
+    pub fn running(&self) -> usize {
+        let jobs = self.jobs.lock().unwrap();
+        jobs.iter().filter(|job| job.status.is_none()).count()
+    }
+
+    pub fn clear_finished(&mut self) {
+        self.jobs.lock().unwrap().retain(|job| job.status.is_none());
+        self.back = 0;
+    }
+
+    /// Runs `command` with `sh -c` in `dir`, collecting stdout and stderr into the log.
+    pub fn spawn(
+        &mut self,
+        command: OsString,
+        dir: &Path,
+        files: Arc<Mutex<FileStruct>>,
+        tx: Sender<String>,
+    ) {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.back = 0;
+        self.jobs.lock().unwrap().push(Job {
+            id,
+            command: command.to_string_lossy().into_owned(),
+            output: VecDeque::new(),
+            status: None,
+        });
+
+        let jobs = Arc::clone(&self.jobs);
+        let dir = dir.to_path_buf();
+        tokio::spawn(async move {
+            let status = run(&command, &dir, id, &jobs, &tx).await;
+            if let Some(job) = jobs.lock().unwrap().iter_mut().find(|job| job.id == id) {
+                job.status = Some(status.clone());
+            }
+            let mut file_struct = files.lock().unwrap();
+            file_struct.refresh();
+            if let Err(status) = status {
+                file_struct.error = Some(Error::other(format!(
+                    "shell command {}, press <J> for the log",
+                    status
+                )));
+            }
+            let _ = tx.try_send(String::from("Shell command finished"));
+        });
+    }
+}
+
+async fn run(
+    command: &OsStr,
+    dir: &Path,
+    id: usize,
+    jobs: &Mutex<Vec<Job>>,
+    tx: &Sender<String>,
+) -> Result<(), String> {
+    let mut script = OsString::from("exec 2>&1\n");
+    script.push(command);
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(script)
+        .current_dir(dir)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|error| format!("failed to start: {}", error))?;
+
+    if let Some(stdout) = child.stdout.take() {
+        let mut reader = BufReader::new(stdout);
+        let mut line = Vec::new();
+        while reader.read_until(b'\n', &mut line).await.unwrap_or(0) > 0 {
+            let text = String::from_utf8_lossy(&line);
+            let text = text.trim_end_matches(['\n', '\r']).replace('\t', "    ");
+            line.clear();
+            if let Some(job) = jobs.lock().unwrap().iter_mut().find(|job| job.id == id) {
+                if job.output.len() == MAX_JOB_LINES {
+                    job.output.pop_front();
+                }
+                job.output.push_back(text);
+            }
+            let _ = tx.try_send(String::new());
+        }
+    }
+
+    match child.wait().await {
+        Ok(status) if status.success() => Ok(()),
+        Ok(status) => Err(format!("exited with {}", status)),
+        Err(error) => Err(error.to_string()),
+    }
+}
+
+/// Expands `%f` (selected path), `%s` (marked paths, or the selected one), `%d` (current
+/// directory) and `%%`, shell-quoting every path.
+pub fn expand(template: &str, selected: Option<&Path>, marked: &[PathBuf], pwd: &Path) -> OsString {
+    let mut expanded = OsString::with_capacity(template.len());
+    let mut chars = template.chars();
+    while let Some(ch) = chars.next() {
+        if ch != '%' {
+            expanded.push(ch.encode_utf8(&mut [0; 4]));
+            continue;
+        }
+        match chars.next() {
+            Some('f') => {
+                if let Some(path) = selected {
+                    expanded.push(quote(path));
+                }
+            }
+            Some('s') => {
+                for (index, path) in marked.iter().enumerate() {
+                    if index > 0 {
+                        expanded.push(" ");
+                    }
+                    expanded.push(quote(path));
+                }
+            }
+            Some('d') => expanded.push(quote(pwd)),
+            Some('%') => expanded.push("%"),
+            Some(other) => {
+                expanded.push("%");
+                expanded.push(other.encode_utf8(&mut [0; 4]));
+            }
+            None => expanded.push("%"),
+        }
+    }
+    expanded
+}
+
+/// Single-quotes `path` for `sh`, keeping non-UTF-8 bytes intact.
+#[cfg(unix)]
+fn quote(path: &Path) -> OsString {
+    use std::os::unix::ffi::{OsStrExt, OsStringExt};
+
+    let mut quoted = vec![b'\''];
+    for &byte in path.as_os_str().as_bytes() {
+        match byte {
+            b'\'' => quoted.extend_from_slice(br"'\''"),
+            byte => quoted.push(byte),
+        }
+    }
+    quoted.push(b'\'');
+    OsString::from_vec(quoted)
+}
+
+#[cfg(not(unix))]
+fn quote(path: &Path) -> OsString {
+    format!("'{}'", path.to_string_lossy().replace('\'', r"'\''")).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand_with(template: &str, marked: &[&str]) -> OsString {
+        let marked: Vec<PathBuf> = marked.iter().map(PathBuf::from).collect();
+        expand(
+            template,
+            Some(Path::new("/tmp/a b.txt")),
+            &marked,
+            Path::new("/home/me"),
+        )
+    }
+
+    #[test]
+    fn expands_placeholders() {
+        assert_eq!(
+            expand_with("cp %f %d", &[]),
+            OsStr::new("cp '/tmp/a b.txt' '/home/me'")
+        );
+        assert_eq!(
+            expand_with("tar cf out.tar %s", &["x", "y z"]),
+            OsStr::new("tar cf out.tar 'x' 'y z'")
+        );
+    }
+
+    #[test]
+    fn expands_empty_marks_to_nothing() {
+        assert_eq!(expand_with("ls %s", &[]), OsStr::new("ls "));
+    }
+
+    #[test]
+    fn keeps_literal_percent_signs() {
+        assert_eq!(
+            expand_with("date +%%Y %q 100%", &[]),
+            OsStr::new("date +%Y %q 100%")
+        );
+    }
+
+    #[test]
+    fn quotes_single_quotes() {
+        assert_eq!(quote(Path::new("it's here")), OsStr::new(r"'it'\''s here'"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn quotes_non_utf8_paths_verbatim() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let path = Path::new(OsStr::from_bytes(b"bad\xffname"));
+        assert_eq!(quote(path).as_bytes(), b"'bad\xffname'");
+    }
+}
@@ -0,0 +1,132 @@
+use std::{
+    mem,
+    sync::{Arc, Mutex},
+};
+
+use crate::{
+    explorer::FileStruct,
+    ui::{FileScout, PaneLayout, ViewMode},
+};
+
+pub struct Tab {
+    pub files: Arc<Mutex<FileStruct>>,
+    pub mode: ViewMode,
+    pub text_scroll_y: usize,
+    pub text_scroll_x: usize,
+}
+
+impl Tab {
+    pub fn new(files: Arc<Mutex<FileStruct>>) -> Self {
+        Self {
+            files,
+            mode: ViewMode::ListView,
+            text_scroll_y: 0,
+            text_scroll_x: 0,
+        }
+    }
+}
+
+impl FileScout {
+    pub fn open_tab(&mut self, file_struct: &FileStruct) {
+        let index = self.new_tab(file_struct);
+        self.switch_tab(index);
+    }
+
+    fn new_tab(&mut self, file_struct: &FileStruct) -> usize {
+        let mut files = FileStruct::default();
+        let pwd = file_struct.pwd.to_path_buf();
+        files.present_dir_fn(&pwd, file_struct.current_state.selected());
+        let index = self.active_tab + 1;
+        self.tabs
+            .insert(index, Tab::new(Arc::new(Mutex::new(files))));
+        if self.partner_tab >= index {
+            self.partner_tab += 1;
+        }
+        index
+    }
+
+    pub fn toggle_layout(&mut self, file_struct: &FileStruct) {
+        self.layout = match self.layout {
+            PaneLayout::Miller => {
+                if self.tabs.len() < 2 {
+                    self.new_tab(file_struct);
+                }
+                if self.partner_tab == self.active_tab || self.partner_tab >= self.tabs.len() {
+                    self.partner_tab = (self.active_tab + 1) % self.tabs.len();
+                }
+                PaneLayout::DualPane
+            }
+            PaneLayout::DualPane => PaneLayout::Miller,
+        };
+    }
+
+    pub fn switch_pane(&mut self) {
+        if self.layout == PaneLayout::DualPane {
+            self.switch_tab(self.partner_tab);
+        }
+    }
+
+    pub fn close_tab(&mut self) {
+        if self.tabs.len() < 2 {
+            return;
+        }
+        self.tabs.remove(self.active_tab);
+        if self.partner_tab > self.active_tab {
+            self.partner_tab -= 1;
+        }
+        let index = self.active_tab.min(self.tabs.len() - 1);
+        self.active_tab = index;
+        if self.tabs.len() < 2 {
+            self.layout = PaneLayout::Miller;
+        } else if self.partner_tab == index {
+            self.partner_tab = (index + 1) % self.tabs.len();
+        }
+        self.restore_tab(index);
+    }
+
+    pub fn switch_tab(&mut self, index: usize) {
+        if index >= self.tabs.len() || index == self.active_tab {
+            return;
+        }
+        let tab = &mut self.tabs[self.active_tab];
+        tab.mode = mem::replace(&mut self.mode, ViewMode::ListView);
+        tab.text_scroll_y = self.text_scroll_y;
+        tab.text_scroll_x = self.text_scroll_x;
+        if index == self.partner_tab {
+            self.partner_tab = self.active_tab;
+        }
+        self.active_tab = index;
+        self.restore_tab(index);
+    }
+
+    pub fn other_tab(&self) -> Option<Arc<Mutex<FileStruct>>> {
+        if self.tabs.len() < 2 {
+            return None;
+        }
+        let index = match self.layout {
+            PaneLayout::Miller => (self.active_tab + 1) % self.tabs.len(),
+            PaneLayout::DualPane => self.partner_tab,
+        };
+        Some(Arc::clone(&self.tabs[index].files))
+    }
+
+    /// The file structs on screen, the focused one first.
+    pub fn visible_panes(&self) -> Vec<Arc<Mutex<FileStruct>>> {
+        let mut panes = vec![Arc::clone(&self.files)];
+        if self.layout == PaneLayout::DualPane && self.partner_tab != self.active_tab {
+            if let Some(tab) = self.tabs.get(self.partner_tab) {
+                panes.push(Arc::clone(&tab.files));
+            }
+        }
+        panes
+    }
+
+    fn restore_tab(&mut self, index: usize) {
+        let tab = &mut self.tabs[index];
+        self.files = Arc::clone(&tab.files);
+        self.mode = mem::replace(&mut tab.mode, ViewMode::ListView);
+        self.text_scroll_y = tab.text_scroll_y;
+        self.text_scroll_x = tab.text_scroll_x;
+        self.files.lock().unwrap().refresh();
+    }
+}
@@ -0,0 +1,568 @@
+use std::{collections::VecDeque, ops::Range};
+
+use ropey::{Rope, RopeSlice};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+const MAX_HISTORY: usize = 1000;
+
+pub type Cursor = (usize, usize);
+
+struct Splice {
+    index: usize,
+    removed: String,
+    inserted: String,
+}
+
+impl Splice {
+    fn extend(&mut self, next: &Splice) -> bool {
+        let typing = self.removed.is_empty() && next.removed.is_empty();
+        let deleting = self.inserted.is_empty() && next.inserted.is_empty();
+        if typing
+            && self.index + self.inserted.chars().count() == next.index
+            && !self.inserted.contains('\n')
+            && !next.inserted.contains('\n')
+        {
+            self.inserted.push_str(&next.inserted);
+        } else if deleting && next.index + next.removed.chars().count() == self.index {
+            self.index = next.index;
+            self.removed.insert_str(0, &next.removed);
+        } else if deleting && next.index == self.index {
+            self.removed.push_str(&next.removed);
+        } else {
+            return false;
+        }
+        true
+    }
+}
+
+/// One undo step; its splices apply in order, each against the text left by the previous one.
+struct Edit {
+    revision: usize,
+    splices: Vec<Splice>,
+    before: Cursor,
+    after: Cursor,
+}
+
+impl Edit {
+    fn extend(&mut self, edit: Edit) -> Option<Edit> {
+        let merged = match (self.splices.as_mut_slice(), edit.splices.as_slice()) {
+            ([last], [next]) => last.extend(next),
+            _ => false,
+        };
+        if !merged {
+            return Some(edit);
+        }
+        self.after = edit.after;
+        self.revision = edit.revision;
+        None
+    }
+}
+
+#[derive(Default)]
+pub struct TextBuffer {
+    rope: Rope,
+    undo: VecDeque<Edit>,
+    redo: Vec<Edit>,
+    merge: bool,
+    /// Revision of the oldest state undo can return to.
+    base: usize,
+    last_revision: usize,
+}
+
+fn trim_line_break(line: RopeSlice) -> RopeSlice {
+    let mut len = line.len_chars();
+    if len > 0 && line.char(len - 1) == '\n' {
+        len -= 1;
+    }
+    if len > 0 && line.char(len - 1) == '\r' {
+        len -= 1;
+    }
+    line.slice(..len)
+}
+
+fn word_class(grapheme: &str) -> u8 {
+    match grapheme.chars().next() {
+        Some(ch) if ch.is_whitespace() => 0,
+        Some(ch) if ch.is_alphanumeric() || ch == '_' => 1,
+        _ => 2,
+    }
+}
+
+impl TextBuffer {
+    pub fn new(text: &str) -> Self {
+        Self {
+            rope: Rope::from_str(text),
+            ..Default::default()
+        }
+    }
+
+    pub fn text(&self) -> String {
+        self.rope.to_string()
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.rope.len_lines()
+    }
+
+    pub fn line(&self, line: usize) -> String {
+        if line >= self.line_count() {
+            return String::new();
+        }
+        trim_line_break(self.rope.line(line)).to_string()
+    }
+
+    pub fn line_len(&self, line: usize) -> usize {
+        self.line(line).graphemes(true).count()
+    }
+
+    pub fn display_column(&self, line: usize, column: usize) -> usize {
+        self.line(line)
+            .graphemes(true)
+            .take(column)
+            .map(|grapheme| grapheme.width())
+            .sum()
+    }
+
+    pub fn visible(&self, line: usize, left: usize, width: usize) -> String {
+        let mut visible = String::new();
+        let mut column = 0;
+        for grapheme in self.line(line).graphemes(true) {
+            let end = column + grapheme.width();
+            if end > left + width {
+                visible.extend(std::iter::repeat_n(
+                    ' ',
+                    (left + width).saturating_sub(column),
+                ));
+                break;
+            }
+            if column >= left {
+                visible.push_str(grapheme);
+            } else if end > left {
+                visible.extend(std::iter::repeat_n(' ', end - left));
+            }
+            column = end;
+        }
+        visible
+    }
+
+    pub fn column_of(&self, line: usize, byte: usize) -> usize {
+        let text = self.line(line);
+        text[..byte.min(text.len())].graphemes(true).count()
+    }
+
+    pub fn byte_of(&self, line: usize, column: usize) -> usize {
+        self.line(line)
+            .graphemes(true)
+            .take(column)
+            .map(str::len)
+            .sum()
+    }
+
+    fn char_index(&self, line: usize, column: usize) -> usize {
+        let line = line.min(self.line_count() - 1);
+        let chars: usize = self
+            .line(line)
+            .graphemes(true)
+            .take(column)
+            .map(|grapheme| grapheme.chars().count())
+            .sum();
+        self.rope.line_to_char(line) + chars
+    }
+
+    fn cursor_at(&self, index: usize) -> Cursor {
+        let line = self.rope.char_to_line(index);
+        let prefix = self
+            .rope
+            .slice(self.rope.line_to_char(line)..index)
+            .to_string();
+        (line, prefix.graphemes(true).count())
+    }
+
+    pub fn insert(&mut self, line: usize, column: usize, text: &str) -> Cursor {
+        let index = self.char_index(line, column);
+        let before = self.cursor_at(index);
+        self.rope.insert(index, text);
+
+        let after = self.cursor_at(index + text.chars().count());
+        self.record(
+            vec![Splice {
+                index,
+                removed: String::new(),
+                inserted: text.to_string(),
+            }],
+            before,
+            after,
+        );
+        after
+    }
+
+    pub fn remove_before(&mut self, line: usize, column: usize) -> Option<Cursor> {
+        let column = column.min(self.line_len(line));
+        let index = self.char_index(line, column);
+        if index == 0 {
+            return None;
+        }
+        let start = match column {
+            0 => self.char_index(line - 1, self.line_len(line - 1)),
+            _ => self.char_index(line, column - 1),
+        };
+        Some(self.remove(start, index, (line, column)))
+    }
+
+    pub fn remove_after(&mut self, line: usize, column: usize) -> bool {
+        let column = column.min(self.line_len(line));
+        let index = self.char_index(line, column);
+        let end = if column < self.line_len(line) {
+            self.char_index(line, column + 1)
+        } else if line + 1 < self.line_count() {
+            self.rope.line_to_char(line + 1)
+        } else {
+            return false;
+        };
+        self.remove(index, end, (line, column));
+        true
+    }
+
+    pub fn slice(&self, from: Cursor, to: Cursor) -> String {
+        let start = self.char_index(from.0, from.1);
+        let end = self.char_index(to.0, to.1);
+        self.rope.slice(start..end).to_string()
+    }
+
+    pub fn remove_range(&mut self, from: Cursor, to: Cursor) -> Cursor {
+        let start = self.char_index(from.0, from.1);
+        let end = self.char_index(to.0, to.1);
+        if start == end {
+            return from;
+        }
+        self.remove(start, end, to)
+    }
+
+    pub fn delete_line(&mut self, line: usize) -> Cursor {
+        let line = line.min(self.line_count() - 1);
+        let mut start = self.rope.line_to_char(line);
+        let end = if line + 1 < self.line_count() {
+            self.rope.line_to_char(line + 1)
+        } else {
+            self.rope.len_chars()
+        };
+        if line + 1 == self.line_count() && line > 0 {
+            start = self.char_index(line - 1, self.line_len(line - 1));
+        }
+        if start < end {
+            self.remove(start, end, (line, 0));
+        }
+        (line.min(self.line_count() - 1), 0)
+    }
+
+    pub fn duplicate_line(&mut self, line: usize, line_ending: &str) {
+        let content = self.line(line);
+        self.insert(
+            line,
+            self.line_len(line),
+            &format!("{}{}", line_ending, content),
+        );
+    }
+
+    pub fn swap_lines(&mut self, first: usize) -> bool {
+        if first + 1 >= self.line_count() {
+            return false;
+        }
+        let upper = self.line(first);
+        let lower = self.line(first + 1);
+        let start = self.rope.line_to_char(first);
+        let line_break = self
+            .rope
+            .slice(start + upper.chars().count()..self.rope.line_to_char(first + 1))
+            .to_string();
+        let removed = format!("{}{}{}", upper, line_break, lower);
+        let inserted = format!("{}{}{}", lower, line_break, upper);
+        self.splice(start, &removed, &inserted);
+        self.record(
+            vec![Splice {
+                index: start,
+                removed,
+                inserted,
+            }],
+            (first, 0),
+            (first + 1, 0),
+        );
+        true
+    }
+
+    pub fn word_left(&self, line: usize, column: usize) -> Cursor {
+        if column == 0 {
+            return match line {
+                0 => (0, 0),
+                _ => (line - 1, self.line_len(line - 1)),
+            };
+        }
+        let text = self.line(line);
+        let graphemes: Vec<&str> = text.graphemes(true).collect();
+        let mut column = column.min(graphemes.len());
+        while column > 0 && word_class(graphemes[column - 1]) == 0 {
+            column -= 1;
+        }
+        if column > 0 {
+            let class = word_class(graphemes[column - 1]);
+            while column > 0 && word_class(graphemes[column - 1]) == class {
+                column -= 1;
+            }
+        }
+        (line, column)
+    }
+
+    pub fn word_right(&self, line: usize, column: usize) -> Cursor {
+        let text = self.line(line);
+        let graphemes: Vec<&str> = text.graphemes(true).collect();
+        if column >= graphemes.len() {
+            if line + 1 < self.line_count() {
+                return (line + 1, 0);
+            }
+            return (line, graphemes.len());
+        }
+        let mut column = column;
+        let class = word_class(graphemes[column]);
+        while class != 0 && column < graphemes.len() && word_class(graphemes[column]) == class {
+            column += 1;
+        }
+        while column < graphemes.len() && word_class(graphemes[column]) == 0 {
+            column += 1;
+        }
+        (line, column)
+    }
+
+    fn remove(&mut self, start: usize, end: usize, before: Cursor) -> Cursor {
+        let removed = self.rope.slice(start..end).to_string();
+        self.rope.remove(start..end);
+
+        let after = self.cursor_at(start);
+        self.record(
+            vec![Splice {
+                index: start,
+                removed,
+                inserted: String::new(),
+            }],
+            before,
+            after,
+        );
+        after
+    }
+
+    pub fn replace_range(&mut self, line: usize, range: Range<usize>, text: &str) -> Cursor {
+        let start = self.rope.line_to_char(line);
+        let content = self.line(line);
+        let index = start + content[..range.start].chars().count();
+        let removed = content[range].to_string();
+        let before = self.cursor_at(index);
+        self.splice(index, &removed, text);
+
+        let after = self.cursor_at(index + text.chars().count());
+        self.record(
+            vec![Splice {
+                index,
+                removed,
+                inserted: text.to_string(),
+            }],
+            before,
+            after,
+        );
+        after
+    }
+
+    pub fn replace_lines(
+        &mut self,
+        cursor: Cursor,
+        replace: impl Fn(&str) -> Option<String>,
+    ) -> usize {
+        let mut replaced = 0;
+        let mut splices = Vec::new();
+        // Later splices land in text already shifted by the earlier ones.
+        let (mut grown, mut shrunk) = (0, 0);
+        for (line, slice) in self.rope.lines().enumerate() {
+            let content = trim_line_break(slice).to_string();
+            let Some(new) = replace(&content) else {
+                continue;
+            };
+            replaced += 1;
+            if new == content {
+                continue;
+            }
+            let index = self.rope.line_to_char(line) + grown - shrunk;
+            grown += new.chars().count();
+            shrunk += content.chars().count();
+            splices.push(Splice {
+                index,
+                removed: content,
+                inserted: new,
+            });
+        }
+        if splices.is_empty() {
+            return replaced;
+        }
+
+        for splice in &splices {
+            self.splice(splice.index, &splice.removed, &splice.inserted);
+        }
+        let line = cursor.0.min(self.line_count() - 1);
+        let after = (line, cursor.1.min(self.line_len(line)));
+        self.record(splices, cursor, after);
+        replaced
+    }
+
+    /// Identifies the current content; undoing back to a state restores its revision.
+    pub fn revision(&self) -> usize {
+        self.undo.back().map_or(self.base, |edit| edit.revision)
+    }
+
+    fn record(&mut self, splices: Vec<Splice>, before: Cursor, after: Cursor) {
+        self.redo.clear();
+        self.last_revision += 1;
+        let edit = Edit {
+            revision: self.last_revision,
+            splices,
+            before,
+            after,
+        };
+        let edit = match self.undo.back_mut() {
+            Some(last) if self.merge => last.extend(edit),
+            _ => Some(edit),
+        };
+        if let Some(edit) = edit {
+            if self.undo.len() >= MAX_HISTORY {
+                if let Some(oldest) = self.undo.pop_front() {
+                    self.base = oldest.revision;
+                }
+            }
+            self.undo.push_back(edit);
+        }
+        self.merge = true;
+    }
+
+    pub fn break_undo_group(&mut self) {
+        self.merge = false;
+    }
+
+    fn splice(&mut self, index: usize, old: &str, new: &str) {
+        self.rope.remove(index..index + old.chars().count());
+        self.rope.insert(index, new);
+    }
+
+    pub fn undo(&mut self) -> Option<Cursor> {
+        let edit = self.undo.pop_back()?;
+        for splice in edit.splices.iter().rev() {
+            self.splice(splice.index, &splice.inserted, &splice.removed);
+        }
+        self.merge = false;
+        let cursor = edit.before;
+        self.redo.push(edit);
+        Some(cursor)
+    }
+
+    pub fn redo(&mut self) -> Option<Cursor> {
+        let edit = self.redo.pop()?;
+        for splice in &edit.splices {
+            self.splice(splice.index, &splice.removed, &splice.inserted);
+        }
+        self.merge = false;
+        let cursor = edit.after;
+        self.undo.push_back(edit);
+        Some(cursor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn type_text(buffer: &mut TextBuffer, text: &str) {
+        let mut cursor = (buffer.line_count() - 1, 0);
+        cursor.1 = buffer.line_len(cursor.0);
+        for ch in text.chars() {
+            cursor = buffer.insert(cursor.0, cursor.1, &ch.to_string());
+        }
+    }
+
+    #[test]
+    fn typing_is_one_undo_step() {
+        let mut buffer = TextBuffer::new("");
+        type_text(&mut buffer, "abc");
+        assert_eq!(buffer.undo(), Some((0, 0)));
+        assert_eq!(buffer.text(), "");
+        assert_eq!(buffer.undo(), None);
+        assert_eq!(buffer.redo(), Some((0, 3)));
+        assert_eq!(buffer.text(), "abc");
+    }
+
+    #[test]
+    fn separate_groups_undo_separately() {
+        let mut buffer = TextBuffer::new("");
+        type_text(&mut buffer, "ab");
+        buffer.break_undo_group();
+        type_text(&mut buffer, "cd");
+        buffer.undo();
+        assert_eq!(buffer.text(), "ab");
+        buffer.undo();
+        assert_eq!(buffer.text(), "");
+    }
+
+    #[test]
+    fn undo_to_saved_revision_is_clean() {
+        let mut buffer = TextBuffer::new("hello");
+        let saved = buffer.revision();
+        type_text(&mut buffer, "!");
+        assert_ne!(buffer.revision(), saved);
+        buffer.undo();
+        assert_eq!(buffer.revision(), saved);
+        buffer.redo();
+        assert_ne!(buffer.revision(), saved);
+    }
+
+    #[test]
+    fn merged_edit_after_save_is_dirty() {
+        let mut buffer = TextBuffer::new("");
+        type_text(&mut buffer, "ab");
+        let saved = buffer.revision();
+        type_text(&mut buffer, "c");
+        assert_ne!(buffer.revision(), saved);
+        // The merged step undoes past the save, so neither end of it is the saved text.
+        buffer.undo();
+        assert_eq!(buffer.text(), "");
+        assert_ne!(buffer.revision(), saved);
+        buffer.redo();
+        assert_ne!(buffer.revision(), saved);
+    }
+
+    #[test]
+    fn history_drops_the_oldest_steps() {
+        let mut buffer = TextBuffer::new("");
+        let saved = buffer.revision();
+        let extra = 5;
+        for _ in 0..MAX_HISTORY + extra {
+            buffer.break_undo_group();
+            type_text(&mut buffer, "x");
+        }
+        let mut undone = 0;
+        while buffer.undo().is_some() {
+            undone += 1;
+        }
+        assert_eq!(undone, MAX_HISTORY);
+        assert_eq!(buffer.text(), "x".repeat(extra));
+        assert_ne!(buffer.revision(), saved);
+    }
+
+    #[test]
+    fn replace_lines_is_one_undo_step() {
+        let mut buffer = TextBuffer::new("foo\nbar\nfoo baz\n");
+        let replaced = buffer.replace_lines((0, 0), |line| {
+            line.contains("foo").then(|| line.replace("foo", "quux"))
+        });
+        assert_eq!(replaced, 2);
+        assert_eq!(buffer.text(), "quux\nbar\nquux baz\n");
+        buffer.undo();
+        assert_eq!(buffer.text(), "foo\nbar\nfoo baz\n");
+        buffer.redo();
+        assert_eq!(buffer.text(), "quux\nbar\nquux baz\n");
+    }
+}
@@ -1,6 +1,9 @@
 use std::{
     io::Result,
+    ops::Range,
+    path::PathBuf,
     sync::{Arc, Mutex},
+    time::Duration,
 };
 
 use crossterm::event::{Event, EventStream, KeyEvent, KeyEventKind};
@@ -12,30 +15,124 @@ use ratatui::{
     widgets::{Block, Borders, Clear as Clean, List, ListItem, Paragraph, StatefulWidget, Widget},
     DefaultTerminal, Frame,
 };
-use tokio::sync::mpsc;
+use tokio::{sync::mpsc, time};
 use tokio_stream::StreamExt;
+use unicode_width::UnicodeWidthStr;
 
-use crate::{constant::COLORS, explorer::FileStruct};
+use crate::{
+    archive::{self, ArchiveTask},
+    bookmarks::Bookmarks,
+    constant::COLORS,
+    disk_usage::{human_size, DirSizes, DiskUsage},
+    duplicates::DuplicateFinder,
+    encoding::TextFormat,
+    explorer::{FileStamp, FileStruct, HEX_WIDTH},
+    external::ExternalCommand,
+    find::Finder,
+    highlight::Highlighter,
+    history::Frecency,
+    image_preview::{is_image, ImagePreview},
+    open_with::OpenWith,
+    path_prompt::PathPrompt,
+    search::ContentSearch,
+    shell::ShellLog,
+    tabs::Tab,
+    text_buffer::{Cursor, TextBuffer},
+    watcher::DirWatcher,
+};
+
+const STREAM_TICK: Duration = Duration::from_millis(250);
 
-#[derive(PartialEq)]
+#[derive(PartialEq, Clone, Copy)]
 pub enum ViewMode {
     ListView,
     ContentView,
     Rename,
     Create,
     FileEdit,
+    Search,
+    SearchResults,
+    MarkSet,
+    MarkJump,
+    Bookmarks,
+    BookmarkRename,
+    Jump,
+    GoTo,
+    GoToOffset,
+    CreateArchive,
+    DiskUsage,
+    Duplicates,
+    Find,
+    Replace,
+    OpenWith,
+    Shell,
+    ShellLog,
+}
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum PaneLayout {
+    Miller,
+    DualPane,
 }
 
 #[derive(Default)]
 pub struct Input {
     pub content: String,
-    pub total_lines: usize,
-    pub total_letter: usize,
+    pub format: TextFormat,
+    pub buffer: TextBuffer,
+    /// Buffer revision last loaded from or written to disk.
+    pub saved: usize,
+    pub top: usize,
+    pub left: usize,
+    pub page: usize,
+    pub cursor: Position,
+    pub anchor: Option<Cursor>,
+    pub register: String,
+    pub register_line: bool,
+    pub path: PathBuf,
+    pub stamp: Option<FileStamp>,
+    pub confirm: Option<Confirm>,
+    pub status: Option<String>,
+}
+
+#[derive(Clone, Copy)]
+pub enum Confirm {
+    Quit,
+    Overwrite { quit: bool },
+}
+
+impl Input {
+    pub fn selection(&self, cursor: Cursor) -> Option<(Cursor, Cursor)> {
+        let anchor = self.anchor.filter(|anchor| *anchor != cursor)?;
+        Some((anchor.min(cursor), anchor.max(cursor)))
+    }
+
+    pub fn dirty(&self) -> bool {
+        self.buffer.revision() != self.saved
+    }
 }
 
 pub struct FileScout {
     pub files: Arc<Mutex<FileStruct>>,
+    pub tabs: Vec<Tab>,
+    pub active_tab: usize,
+    pub partner_tab: usize,
+    pub layout: PaneLayout,
     pub input: Input,
+    pub search: ContentSearch,
+    pub finder: Finder,
+    pub bookmarks: Bookmarks,
+    pub frecency: Frecency,
+    pub path_prompt: PathPrompt,
+    pub highlighter: Highlighter,
+    pub images: ImagePreview,
+    pub sizes: DirSizes,
+    pub disk_usage: DiskUsage,
+    pub duplicates: DuplicateFinder,
+    pub archive_task: ArchiveTask,
+    pub external: Option<ExternalCommand>,
+    pub open_with: OpenWith,
+    pub shell: ShellLog,
     pub text_scroll_y: usize,
     pub text_scroll_x: usize,
     pub color_index: usize,
@@ -45,10 +142,29 @@ pub struct FileScout {
 
 impl FileScout {
     pub fn new(files: FileStruct) -> Self {
+        let files = Arc::new(Mutex::new(files));
         Self {
-            files: Arc::new(Mutex::new(files)),
+            tabs: vec![Tab::new(Arc::clone(&files))],
+            active_tab: 0,
+            partner_tab: 0,
+            layout: PaneLayout::Miller,
+            files,
             mode: ViewMode::ListView,
             input: Input::default(),
+            search: ContentSearch::default(),
+            finder: Finder::default(),
+            bookmarks: Bookmarks::load(),
+            frecency: Frecency::load(),
+            path_prompt: PathPrompt::default(),
+            highlighter: Highlighter::default(),
+            images: ImagePreview::default(),
+            sizes: DirSizes::default(),
+            disk_usage: DiskUsage::default(),
+            duplicates: DuplicateFinder::default(),
+            archive_task: ArchiveTask::default(),
+            external: None,
+            open_with: OpenWith::load(),
+            shell: ShellLog::default(),
             text_scroll_y: 0,
             text_scroll_x: 0,
             color_index: 0,
@@ -60,12 +176,70 @@ impl FileScout {
         use crate::key_events::handle_events;
         let mut reader = EventStream::new();
         let (tx, mut rx) = mpsc::channel::<String>(1);
+        let mut tick = time::interval(STREAM_TICK);
+        let mut watcher = match DirWatcher::new(tx.clone()) {
+            Ok(watcher) => Some(watcher),
+            Err(error) => {
+                self.files.lock().unwrap().error = Some(error);
+                None
+            }
+        };
         while !self.exit {
+            if let Some(command) = self.external.take() {
+                drop(reader);
+                ratatui::restore();
+                let result = command.run().await;
+                *terminal = ratatui::init();
+                reader = EventStream::new();
+
+                let mut file_struct = self.files.lock().unwrap();
+                let pwd = file_struct.pwd.to_path_buf();
+                let selected = file_struct.current_state.selected();
+                file_struct.present_dir_fn(&pwd, selected);
+                match result {
+                    Ok(status) => file_struct.status = Some(status),
+                    Err(error) => file_struct.error = Some(error),
+                }
+            }
+            if let Some(watcher) = watcher.as_mut() {
+                watcher.sync(&self.visible_panes());
+                for path in watcher.take_changes() {
+                    self.sizes.forget(&path);
+                }
+            }
+            self.highlighter.sync(&self.files, &tx);
+            self.images.sync(&self.files, &tx);
+            if self.mode == ViewMode::DiskUsage {
+                let root = self.disk_usage.root.to_path_buf();
+                self.sizes.request(&root, &tx);
+                self.disk_usage.update(&self.sizes);
+            } else {
+                self.sizes.sync(&self.files, &tx);
+            }
+            let streaming = {
+                let file_struct = self.files.lock().unwrap();
+                file_struct
+                    .large
+                    .as_ref()
+                    .is_some_and(|large| large.is_streaming())
+                    || self.archive_task.lock().unwrap().is_some()
+                    || (self.mode == ViewMode::DiskUsage && self.sizes.is_pending())
+                    || (self.mode == ViewMode::Duplicates && self.duplicates.is_running())
+            };
+            {
+                let mut file_struct = self.files.lock().unwrap();
+                if !file_struct.in_archive() {
+                    self.frecency.observe(&file_struct.pwd);
+                }
+                if let Err(error) = self.frecency.flush(false) {
+                    file_struct.error = Some(error);
+                }
+            }
             terminal.draw(|frame| {
+                frame.render_widget(&mut self, frame.area());
                 if self.mode == ViewMode::FileEdit {
                     self.handle_curson(frame);
                 }
-                frame.render_widget(&mut self, frame.area());
             })?;
             tokio::select! {
                 Some(Ok(event)) = reader.next() => {
@@ -78,16 +252,19 @@ impl FileScout {
                     }
                 }
                 Some(_) = rx.recv() => continue,
+                _ = tick.tick(), if streaming => continue,
             }
         }
-        Ok(())
+        self.frecency.flush(true)
+    }
+
+    fn is_editing(&self) -> bool {
+        self.mode == ViewMode::FileEdit
+            || matches!(self.finder.origin, Some((ViewMode::FileEdit, ..)))
     }
 
     fn handle_curson(&self, frame: &mut Frame) {
-        frame.set_cursor_position(Position::new(
-            (self.text_scroll_x + 1) as u16,
-            (self.text_scroll_y + 1) as u16,
-        ));
+        frame.set_cursor_position(self.input.cursor);
     }
 }
 
@@ -96,11 +273,31 @@ impl Widget for &mut FileScout {
         let files_struct_clone = Arc::clone(&self.files);
         let mut file_ex = files_struct_clone.lock().unwrap();
 
-        if self.mode == ViewMode::FileEdit {
+        if self.is_editing() {
             self.render_file_content(area, buf, &mut file_ex);
             return;
         }
 
+        if self.mode == ViewMode::SearchResults {
+            self.render_search_results(area, buf, &mut file_ex);
+            return;
+        }
+
+        if self.mode == ViewMode::DiskUsage {
+            self.render_disk_usage(area, buf, &mut file_ex);
+            return;
+        }
+
+        if self.mode == ViewMode::Duplicates {
+            self.render_duplicates(area, buf, &mut file_ex);
+            return;
+        }
+
+        if self.mode == ViewMode::ShellLog {
+            self.render_shell_log(area, buf, &mut file_ex);
+            return;
+        }
+
         let [pwd_area, files_area, message] = Layout::vertical([
             Constraint::Length(1),
             Constraint::Fill(1),
@@ -108,59 +305,304 @@ impl Widget for &mut FileScout {
         ])
         .areas(area);
 
+        self.render_pwd(pwd_area, buf, &mut file_ex);
+        match self.layout {
+            PaneLayout::Miller => self.render_miller(files_area, buf, &mut file_ex),
+            PaneLayout::DualPane => self.render_dual(files_area, buf, &mut file_ex),
+        }
+        self.render_message(message, buf, &mut file_ex);
+
+        match self.mode {
+            ViewMode::Bookmarks | ViewMode::BookmarkRename => {
+                self.render_bookmarks(files_area, buf)
+            }
+            ViewMode::Jump => self.render_jump(files_area, buf),
+            ViewMode::GoTo => self.render_goto(files_area, buf),
+            ViewMode::OpenWith => self.render_open_with(files_area, buf),
+            _ => {}
+        }
+    }
+}
+
+fn popup_area(area: Rect, percent_x: u16, percent_y: u16) -> Rect {
+    let [area] = Layout::horizontal([Constraint::Percentage(percent_x)])
+        .flex(Flex::Center)
+        .areas(area);
+    let [area] = Layout::vertical([Constraint::Percentage(percent_y)])
+        .flex(Flex::Center)
+        .areas(area);
+    area
+}
+
+impl FileScout {
+    fn render_miller(&mut self, area: Rect, buf: &mut Buffer, file_struct: &mut FileStruct) {
         let [parent_dir, current_dir, files] = Layout::horizontal([
             Constraint::Percentage(15),
             Constraint::Fill(1),
             Constraint::Fill(1),
         ])
-        .areas(files_area);
+        .areas(area);
 
-        self.render_pwd(pwd_area, buf, &mut file_ex);
-        self.render_current(current_dir, buf, &mut file_ex);
-        self.render_parent(parent_dir, buf, &mut file_ex);
+        self.render_current(current_dir, buf, file_struct);
+        self.render_parent(parent_dir, buf, file_struct);
+        self.render_preview(files, buf, file_struct);
+    }
+
+    fn render_dual(&mut self, area: Rect, buf: &mut Buffer, file_struct: &mut FileStruct) {
+        let [left, right] =
+            Layout::horizontal([Constraint::Fill(1), Constraint::Fill(1)]).areas(area);
+        let (focused, other) = if self.active_tab < self.partner_tab {
+            (left, right)
+        } else {
+            (right, left)
+        };
+
+        self.render_pane(focused, buf, file_struct, true);
+        if self.mode == ViewMode::ContentView {
+            let block = Block::bordered().border_style(Style::new().fg(COLORS[self.color_index].0));
+            let inner = block.inner(other);
+            block.render(other, buf);
+            self.render_preview(inner, buf, file_struct);
+        } else if let Some(tab) = self.tabs.get(self.partner_tab) {
+            let partner = Arc::clone(&tab.files);
+            self.render_pane(other, buf, &mut partner.lock().unwrap(), false);
+        }
+        self.render_input_popup(focused.inner(Margin::new(1, 1)), buf);
+    }
+
+    fn render_pane(
+        &mut self,
+        area: Rect,
+        buf: &mut Buffer,
+        file_struct: &mut FileStruct,
+        focused: bool,
+    ) {
+        let (sel_color, un_color) = COLORS[self.color_index];
+        let border_style = if focused {
+            Style::new().fg(sel_color)
+        } else {
+            Style::new().fg(un_color)
+        };
+        let block = Block::bordered()
+            .title(
+                archive::display_path(&file_struct.pwd)
+                    .to_string_lossy()
+                    .to_string(),
+            )
+            .border_style(border_style);
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        let files = file_struct.current_dir.iter().map(|name| {
+            let value = name
+                .strip_prefix(&file_struct.pwd)
+                .unwrap()
+                .to_str()
+                .unwrap();
+            let mut value = if file_struct.marked.contains(name) {
+                format!("* {}", value)
+            } else {
+                value.to_string()
+            };
+            if let Some(size) = self.sizes.get(name) {
+                value = format!("{}  {}", value, human_size(size));
+            }
+            if name.is_dir() {
+                ListItem::new(Line::from(value).fg(sel_color))
+            } else {
+                ListItem::new(Line::from(value).fg(un_color))
+            }
+        });
+        let highlight = if focused {
+            Style::new().bg(sel_color).fg(un_color)
+        } else {
+            Style::new().underlined()
+        };
+        let list = List::new(files)
+            .highlight_style(highlight)
+            .scroll_padding(18);
+        if !list.is_empty() {
+            StatefulWidget::render(list, inner, buf, &mut file_struct.current_state);
+        } else {
+            Widget::render(Text::from("No items"), inner, buf);
+        }
+    }
 
-        if !file_ex.current_dir.is_empty() {
-            if let Some(index) = file_ex.current_state.selected() {
-                if file_ex.current_dir[index].is_file() {
-                    self.render_content(files, buf, &mut file_ex);
+    fn render_preview(&mut self, area: Rect, buf: &mut Buffer, file_struct: &mut FileStruct) {
+        if !file_struct.current_dir.is_empty() {
+            if let Some(index) = file_struct.current_state.selected() {
+                if file_struct.current_dir[index].is_file() {
+                    self.render_content(area, buf, file_struct);
                 } else {
-                    self.render_sub(files, buf, &mut file_ex);
+                    self.render_sub(area, buf, file_struct);
                 }
             }
         }
-        self.render_message(message, buf, &mut file_ex);
     }
-}
 
-impl FileScout {
-    fn render_file_content(&self, area: Rect, buf: &mut Buffer, _file_struct: &mut FileStruct) {
-        let (sel_color, ..) = COLORS[self.color_index];
+    fn render_file_content(&mut self, area: Rect, buf: &mut Buffer, _file_struct: &mut FileStruct) {
+        let (sel_color, un_color) = COLORS[self.color_index];
         let instruction = Line::from(vec![
             " Save ".into(),
             " <Ctrl+S> ".blue().bold().fg(sel_color),
             " Quit ".into(),
             " <Ctrl+C> ".blue().bold().fg(sel_color),
         ]);
+        let status = format!(
+            " Ln {}, Col {}{}{} ",
+            self.text_scroll_y + 1,
+            self.text_scroll_x + 1,
+            if self.input.dirty() { " [+]" } else { "" },
+            match &self.input.status {
+                Some(status) => format!(" | {}", status),
+                None => String::new(),
+            }
+        );
+
+        let block = Block::bordered()
+            .title_bottom(Line::from(status.fg(sel_color)).left_aligned())
+            .title_bottom(instruction.centered())
+            .title_bottom(Line::from(format!(" {} ", self.input.format)).right_aligned());
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        let input = &mut self.input;
+        let gutter = input.buffer.line_count().to_string().len() + 1;
+        let height = inner.height as usize;
+        let width = (inner.width as usize).saturating_sub(gutter);
+        input.page = height.max(1);
+        if height == 0 || width == 0 {
+            // No room for text; the scroll maths below assumes at least one cell.
+            input.cursor = inner.as_position();
+            return;
+        }
+        let column = input
+            .buffer
+            .display_column(self.text_scroll_y, self.text_scroll_x);
+        input.top = input
+            .top
+            .min(self.text_scroll_y)
+            .max((self.text_scroll_y + 1).saturating_sub(height));
+        input.left = if column < width {
+            0
+        } else {
+            input.left.min(column).max(column + 1 - width)
+        };
+        input.cursor = Position::new(
+            inner.x + (gutter + column - input.left) as u16,
+            inner.y + (self.text_scroll_y - input.top) as u16,
+        );
+
+        let lines: Vec<Line> = (input.top..input.top + height)
+            .take_while(|&line| line < input.buffer.line_count())
+            .map(|line| {
+                Line::from(vec![
+                    format!("{:>1$} ", line + 1, gutter - 1).dark_gray(),
+                    input.buffer.visible(line, input.left, width).into(),
+                ])
+            })
+            .collect();
+        Paragraph::new(lines).render(inner, buf);
 
-        let block = Block::bordered().title_bottom(instruction.centered());
-        let text = Text::from(self.input.content.to_string());
-        Paragraph::new(text).block(block).render(area, buf);
+        let text_area = Rect {
+            x: inner.x + gutter as u16,
+            width: width as u16,
+            ..inner
+        };
+        let cursor = (self.text_scroll_y, self.text_scroll_x);
+        if let Some((start, end)) = input.selection(cursor) {
+            for line in start.0.max(input.top)..=end.0.min(input.top + height) {
+                let text = input.buffer.line(line);
+                let from = if line == start.0 {
+                    input.buffer.byte_of(line, start.1)
+                } else {
+                    0
+                };
+                let to = if line == end.0 {
+                    input.buffer.byte_of(line, end.1)
+                } else {
+                    text.len()
+                };
+                let style = Style::new().bg(sel_color).fg(un_color);
+                highlight_range(
+                    buf,
+                    text_area,
+                    line - input.top,
+                    &text,
+                    from..to,
+                    input.left,
+                    style,
+                );
+            }
+        }
+        if self.finder.is_active() {
+            let cursor = input.buffer.byte_of(self.text_scroll_y, self.text_scroll_x);
+            for line in
+                (input.top..input.top + height).take_while(|&line| line < input.buffer.line_count())
+            {
+                let text = input.buffer.line(line);
+                let matches = self.finder.matches(&text);
+                let current = matches
+                    .iter()
+                    .find(|range| line == self.text_scroll_y && range.start == cursor);
+                highlight_matches(
+                    buf,
+                    text_area,
+                    line - input.top,
+                    &text,
+                    &matches,
+                    current,
+                    input.left,
+                );
+            }
+        }
+        self.render_input_popup(inner, buf);
+        match self.input.confirm {
+            Some(Confirm::Quit) => self.render_popup(
+                inner,
+                buf,
+                " Unsaved changes ",
+                "Save before closing? [Y] save  [N] discard  [Esc] keep editing",
+            ),
+            Some(Confirm::Overwrite { .. }) => self.render_popup(
+                inner,
+                buf,
+                " File changed on disk ",
+                "It changed since it was opened. Overwrite it? [Y] overwrite  [N] cancel",
+            ),
+            None => {}
+        }
     }
 
     fn render_pwd(&self, area: Rect, buf: &mut Buffer, file_struct: &mut FileStruct) {
-        let (sel_color, ..) = COLORS[self.color_index];
-        Paragraph::new(Text::from(file_struct.pwd.to_str().unwrap().fg(sel_color)))
-            .render(area, buf);
+        let (sel_color, un_color) = COLORS[self.color_index];
+        let mut spans = Vec::new();
+        if self.tabs.len() > 1 {
+            for (index, tab) in self.tabs.iter().enumerate() {
+                if index == self.active_tab {
+                    let label = format!(" {}:{} ", index + 1, file_struct.dir_name());
+                    spans.push(label.bg(sel_color).fg(un_color));
+                } else {
+                    let label = format!(" {}:{} ", index + 1, tab.files.lock().unwrap().dir_name());
+                    spans.push(label.fg(sel_color));
+                }
+            }
+            spans.push(" ".into());
+        }
+        let pwd = archive::display_path(&file_struct.pwd);
+        spans.push(pwd.to_string_lossy().to_string().fg(sel_color));
+        Paragraph::new(Line::from(spans)).render(area, buf);
     }
 
     fn render_parent(&mut self, area: Rect, buf: &mut Buffer, file_struct: &mut FileStruct) {
         let padded_area = area.inner(Margin::new(1, 0));
         let (sel_color, un_color) = COLORS[self.color_index];
 
+        let entry = archive::entry_of(&file_struct.pwd);
         let selected_index = file_struct
             .parent_dir
             .iter()
-            .position(|path| file_struct.pwd == *path);
+            .position(|path| entry == *path);
 
         file_struct.parent_state.select(selected_index);
         let files = file_struct.parent_dir.iter().map(|name| {
@@ -198,6 +640,14 @@ impl FileScout {
                 .unwrap()
                 .to_str()
                 .unwrap();
+            let mut value = if file_struct.marked.contains(name) {
+                format!("* {}", value)
+            } else {
+                value.to_string()
+            };
+            if let Some(size) = self.sizes.get(name) {
+                value = format!("{}  {}", value, human_size(size));
+            }
             if name.is_dir() {
                 ListItem::new(Line::from(value).fg(sel_color))
             } else {
@@ -213,14 +663,56 @@ impl FileScout {
             Widget::render(Text::from("No items"), padded_area, buf);
         }
 
+        self.render_input_popup(padded_area, buf);
+    }
+
+    fn render_input_popup(&mut self, area: Rect, buf: &mut Buffer) {
         match self.mode {
-            ViewMode::Rename => self.render_window(padded_area, buf, " Rename "),
-            ViewMode::Create => self.render_window(padded_area, buf, " New File "),
+            ViewMode::Rename => self.render_window(area, buf, " Rename "),
+            ViewMode::Create => self.render_window(area, buf, " New File "),
+            ViewMode::GoToOffset => self.render_window(area, buf, " Go to offset / line "),
+            ViewMode::Shell => self.render_window(area, buf, " Shell (%f %s %d, & = background) "),
+            ViewMode::CreateArchive => {
+                self.render_window(area, buf, " New Archive (.zip .tar .tar.gz .tar.zst .7z) ")
+            }
+            ViewMode::Search => {
+                let title = format!(
+                    " Search [regex: {} | ignore case: {}] ",
+                    if self.search.regex { "on" } else { "off" },
+                    if self.search.ignore_case { "on" } else { "off" },
+                );
+                self.render_window(area, buf, &title)
+            }
+            ViewMode::Find => {
+                let title = format!(
+                    " Find [regex: {} | ignore case: {}]{} ",
+                    if self.finder.regex { "on" } else { "off" },
+                    if self.finder.ignore_case { "on" } else { "off" },
+                    if self.finder.invalid {
+                        " invalid pattern"
+                    } else {
+                        ""
+                    },
+                );
+                self.render_window(area, buf, &title)
+            }
+            ViewMode::Replace => {
+                let title = format!(
+                    " Replace \"{}\" [Enter: replace and find next | Alt+A: replace all] ",
+                    self.finder.query
+                );
+                self.render_window(area, buf, &title)
+            }
             _ => {}
         }
     }
 
     fn render_window(&mut self, area: Rect, buf: &mut Buffer, title: &str) {
+        let content = self.input.content.to_string();
+        self.render_popup(area, buf, title, &content);
+    }
+
+    fn render_popup(&self, area: Rect, buf: &mut Buffer, title: &str, content: &str) {
         let (sel_color, _) = COLORS[self.color_index];
         let [window] = Layout::horizontal([Constraint::Percentage(80)]).areas(area);
         let [window] = Layout::vertical([Constraint::Length(3)])
@@ -234,9 +726,442 @@ impl FileScout {
             .title_alignment(Alignment::Left)
             .border_style(Style::new().fg(sel_color));
 
-        Paragraph::new(self.input.content.as_str())
-            .block(block)
-            .render(window, buf);
+        Paragraph::new(content).block(block).render(window, buf);
+    }
+
+    fn render_search_results(
+        &mut self,
+        area: Rect,
+        buf: &mut Buffer,
+        file_struct: &mut FileStruct,
+    ) {
+        let (sel_color, un_color) = COLORS[self.color_index];
+        let [title_area, list_area, message] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Fill(1),
+            Constraint::Length(1),
+        ])
+        .areas(area);
+
+        let results = Arc::clone(&self.search.results);
+        let results = results.lock().unwrap();
+        let status = if results.running {
+            "searching..."
+        } else if results.truncated {
+            "stopped, too many matches"
+        } else {
+            "done"
+        };
+        let title = format!(
+            "Search \"{}\" in {}: {} matches ({})",
+            self.search.query,
+            file_struct.pwd.to_str().unwrap(),
+            results.hits.len(),
+            status
+        );
+        Paragraph::new(Text::from(title.fg(sel_color))).render(title_area, buf);
+
+        let hits = results.hits.iter().map(|hit| {
+            let path = hit
+                .path
+                .strip_prefix(&file_struct.pwd)
+                .unwrap_or(&hit.path)
+                .to_str()
+                .unwrap_or_default();
+            ListItem::new(Line::from(vec![
+                format!("{}:{}: ", path, hit.line).fg(sel_color),
+                hit.snippet.as_str().fg(un_color),
+            ]))
+        });
+        let list = List::new(hits)
+            .highlight_style(Style::new().bg(sel_color).fg(un_color))
+            .scroll_padding(18);
+        if list.is_empty() {
+            Widget::render(Text::from("No matches").fg(sel_color), list_area, buf);
+        } else {
+            StatefulWidget::render(list, list_area, buf, &mut self.search.state);
+        }
+
+        let instruction = Line::from(vec![
+            " Open ".into(),
+            " <Enter> ".bold().fg(sel_color),
+            " Back ".into(),
+            " <Esc> ".bold().fg(sel_color),
+        ]);
+        Paragraph::new(instruction).render(message, buf);
+    }
+
+    fn render_disk_usage(&mut self, area: Rect, buf: &mut Buffer, file_struct: &mut FileStruct) {
+        let (sel_color, un_color) = COLORS[self.color_index];
+        let [title_area, list_area, message] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Fill(1),
+            Constraint::Length(1),
+        ])
+        .areas(area);
+
+        let usage = &mut self.disk_usage;
+        let status = if self.sizes.is_pending() {
+            " (calculating...)"
+        } else {
+            ""
+        };
+        let title = format!(
+            "Disk usage of {}: {}{}",
+            archive::display_path(&usage.root).to_string_lossy(),
+            human_size(usage.total()),
+            status
+        );
+        Paragraph::new(Text::from(title.fg(sel_color))).render(title_area, buf);
+
+        const BAR_WIDTH: usize = 20;
+        let largest = usage
+            .entries
+            .iter()
+            .filter_map(|entry| entry.size)
+            .max()
+            .unwrap_or_default();
+        let items = usage.entries.iter().map(|entry| {
+            let name = entry
+                .path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string();
+            let (size, bar) = match entry.size {
+                Some(size) => {
+                    let filled = (size as f64 / largest.max(1) as f64 * BAR_WIDTH as f64).round();
+                    (human_size(size), "#".repeat(filled as usize))
+                }
+                None => (String::from("..."), String::new()),
+            };
+            let line = format!("{:>10} [{:<BAR_WIDTH$}] ", size, bar);
+            if entry.is_dir {
+                ListItem::new(Line::from(vec![
+                    line.fg(un_color),
+                    format!("{}/", name).fg(sel_color),
+                ]))
+            } else {
+                ListItem::new(Line::from(vec![line.fg(un_color), name.fg(un_color)]))
+            }
+        });
+        let list = List::new(items)
+            .highlight_style(Style::new().bg(sel_color).fg(un_color))
+            .scroll_padding(18);
+        if list.is_empty() {
+            Widget::render(Text::from("No items").fg(sel_color), list_area, buf);
+        } else {
+            StatefulWidget::render(list, list_area, buf, &mut usage.state);
+        }
+
+        let instruction = Line::from(vec![
+            " Open ".into(),
+            " <Enter> ".bold().fg(sel_color),
+            " Up ".into(),
+            " <Left> ".bold().fg(sel_color),
+            " Delete ".into(),
+            " <Del> ".bold().fg(sel_color),
+            " Rescan ".into(),
+            " <R> ".bold().fg(sel_color),
+            " Back ".into(),
+            " <Esc> ".bold().fg(sel_color),
+        ]);
+        Paragraph::new(instruction).render(message, buf);
+        if let Some(error) = &file_struct.error {
+            Paragraph::new(Text::from(error.to_string()).bold())
+                .right_aligned()
+                .render(message, buf);
+        }
+    }
+
+    fn render_duplicates(&mut self, area: Rect, buf: &mut Buffer, file_struct: &mut FileStruct) {
+        let (sel_color, un_color) = COLORS[self.color_index];
+        let [title_area, list_area, message] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Fill(1),
+            Constraint::Length(1),
+        ])
+        .areas(area);
+
+        let finder = &mut self.duplicates;
+        let rows = finder.rows();
+        let results = Arc::clone(&finder.results);
+        let results = results.lock().unwrap();
+        let status = if !results.running {
+            String::from("done")
+        } else if results.total == 0 {
+            format!("{} {} files...", results.phase, results.done)
+        } else {
+            format!("{} {}/{}...", results.phase, results.done, results.total)
+        };
+        let wasted: u64 = results.groups.iter().map(|group| group.wasted()).sum();
+        let title = format!(
+            "Duplicates in {}: {} groups, {} reclaimable ({})",
+            archive::display_path(&finder.root).to_string_lossy(),
+            results.groups.len(),
+            human_size(wasted),
+            status
+        );
+        Paragraph::new(Text::from(title.fg(sel_color))).render(title_area, buf);
+
+        let items = rows.iter().map(|&(group, file)| {
+            let group = &results.groups[group];
+            match file {
+                None => ListItem::new(Line::from(
+                    format!(
+                        "{} copies of {} ({} wasted)",
+                        group.files.len(),
+                        human_size(group.size),
+                        human_size(group.wasted())
+                    )
+                    .fg(sel_color),
+                )),
+                Some(file) => {
+                    let path = &group.files[file];
+                    let check = if finder.marked.contains(path) {
+                        "[x]"
+                    } else {
+                        "[ ]"
+                    };
+                    let name = path.strip_prefix(&finder.root).unwrap_or(path);
+                    ListItem::new(Line::from(
+                        format!("  {} {}", check, name.to_string_lossy()).fg(un_color),
+                    ))
+                }
+            }
+        });
+        let list = List::new(items)
+            .highlight_style(Style::new().bg(sel_color).fg(un_color))
+            .scroll_padding(18);
+        if list.is_empty() {
+            Widget::render(Text::from("No duplicates").fg(sel_color), list_area, buf);
+        } else {
+            StatefulWidget::render(list, list_area, buf, &mut finder.state);
+        }
+
+        let instruction = Line::from(vec![
+            " Mark ".into(),
+            " <Space> ".bold().fg(sel_color),
+            " Mark extras ".into(),
+            " <A> ".bold().fg(sel_color),
+            " Trash ".into(),
+            " <Del> ".bold().fg(sel_color),
+            " Hard link ".into(),
+            " <H> ".bold().fg(sel_color),
+            " Reveal ".into(),
+            " <Enter> ".bold().fg(sel_color),
+            " Back ".into(),
+            " <Esc> ".bold().fg(sel_color),
+        ]);
+        Paragraph::new(instruction).render(message, buf);
+        if let Some(error) = &file_struct.error {
+            Paragraph::new(Text::from(error.to_string()).bold())
+                .right_aligned()
+                .render(message, buf);
+        }
+    }
+
+    fn render_shell_log(&mut self, area: Rect, buf: &mut Buffer, file_struct: &mut FileStruct) {
+        let (sel_color, un_color) = COLORS[self.color_index];
+        let [title_area, log_area, message] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Fill(1),
+            Constraint::Length(1),
+        ])
+        .areas(area);
+
+        let running = self.shell.running();
+        let jobs = Arc::clone(&self.shell.jobs);
+        let jobs = jobs.lock().unwrap();
+        let title = format!("Shell jobs: {} running, {} total", running, jobs.len());
+        Paragraph::new(Text::from(title.fg(sel_color))).render(title_area, buf);
+
+        let mut lines: Vec<Line> = Vec::new();
+        for job in jobs.iter() {
+            let status = match &job.status {
+                None => "[running]".fg(sel_color),
+                Some(Ok(())) => "[done]".fg(sel_color),
+                Some(Err(status)) => format!("[{}]", status).red(),
+            };
+            lines.push(Line::from(vec![
+                status,
+                format!(" $ {}", job.command).bold().fg(sel_color),
+            ]));
+            lines.extend(
+                job.output
+                    .iter()
+                    .map(|line| Line::from(line.as_str()).fg(un_color)),
+            );
+            lines.push(Line::default());
+        }
+        let height = log_area.height as usize;
+        self.shell.page = height;
+        let bottom = lines.len().saturating_sub(height);
+        self.shell.back = self.shell.back.min(bottom);
+        if lines.is_empty() {
+            Widget::render(
+                Text::from("No shell commands yet, end a <!> command with & to run it here")
+                    .fg(sel_color),
+                log_area,
+                buf,
+            );
+        } else {
+            let scroll = u16::try_from(bottom - self.shell.back).unwrap_or(u16::MAX);
+            Paragraph::new(lines)
+                .scroll((scroll, 0))
+                .render(log_area, buf);
+        }
+
+        let instruction = Line::from(vec![
+            " Scroll ".into(),
+            " <Up/Down> ".bold().fg(sel_color),
+            " Clear finished ".into(),
+            " <C> ".bold().fg(sel_color),
+            " Back ".into(),
+            " <Esc> ".bold().fg(sel_color),
+        ]);
+        Paragraph::new(instruction).render(message, buf);
+        if let Some(error) = &file_struct.error {
+            Paragraph::new(Text::from(error.to_string()).bold())
+                .right_aligned()
+                .render(message, buf);
+        }
+    }
+
+    fn render_bookmarks(&mut self, area: Rect, buf: &mut Buffer) {
+        let (sel_color, un_color) = COLORS[self.color_index];
+        let window = popup_area(area, 60, 60);
+        Clean.render(window, buf);
+
+        let instruction = Line::from(vec![
+            " Jump ".into(),
+            " <Enter> ".bold().fg(sel_color),
+            " Rename ".into(),
+            " <R> ".bold().fg(sel_color),
+            " Delete ".into(),
+            " <Del> ".bold().fg(sel_color),
+        ]);
+        let block = Block::bordered()
+            .title(" Bookmarks ")
+            .title_bottom(instruction.centered())
+            .border_style(Style::new().fg(sel_color));
+        let inner = block.inner(window);
+        block.render(window, buf);
+
+        let items = self.bookmarks.entries.iter().map(|bookmark| {
+            let path = bookmark.path.to_string_lossy();
+            let line = format!("{}  {:<16} {}", bookmark.key, bookmark.name, path);
+            if bookmark.is_broken() {
+                ListItem::new(Line::from(format!("{} (broken)", line)).red())
+            } else {
+                ListItem::new(Line::from(line).fg(un_color))
+            }
+        });
+        let list = List::new(items).highlight_style(Style::new().bg(sel_color).fg(un_color));
+        if list.is_empty() {
+            Widget::render(
+                Text::from("No bookmarks, press <M> and a letter to add one").fg(sel_color),
+                inner,
+                buf,
+            );
+        } else {
+            StatefulWidget::render(list, inner, buf, &mut self.bookmarks.state);
+        }
+
+        if self.mode == ViewMode::BookmarkRename {
+            self.render_window(inner, buf, " Rename Bookmark ");
+        }
+    }
+
+    fn render_open_with(&mut self, area: Rect, buf: &mut Buffer) {
+        let (sel_color, un_color) = COLORS[self.color_index];
+        let window = popup_area(area, 60, 60);
+        Clean.render(window, buf);
+
+        let instruction = Line::from(vec![
+            " Open ".into(),
+            " <Enter> ".bold().fg(sel_color),
+            " Cancel ".into(),
+            " <Esc> ".bold().fg(sel_color),
+        ]);
+        let title = match self.open_with.files.as_slice() {
+            [file] => format!(
+                " Open {} with ",
+                file.file_name().unwrap_or_default().to_string_lossy()
+            ),
+            files => format!(" Open {} files with ", files.len()),
+        };
+        let block = Block::bordered()
+            .title(title)
+            .title_bottom(instruction.centered())
+            .border_style(Style::new().fg(sel_color));
+        let inner = block.inner(window);
+        block.render(window, buf);
+
+        let rules = &self.open_with.rules;
+        let items = self.open_with.choices.iter().map(|index| {
+            let rule = &rules[*index];
+            let kind = if rule.terminal { "term" } else { "gui" };
+            let line = format!(
+                "{:<28} {:<4}  {}",
+                rule.command.join(" "),
+                kind,
+                rule.pattern
+            );
+            ListItem::new(Line::from(line).fg(un_color))
+        });
+        let list = List::new(items).highlight_style(Style::new().bg(sel_color).fg(un_color));
+        StatefulWidget::render(list, inner, buf, &mut self.open_with.state);
+    }
+
+    fn render_prompt_window(&self, area: Rect, buf: &mut Buffer, title: &str) -> Rect {
+        let (sel_color, _) = COLORS[self.color_index];
+        let window = popup_area(area, 60, 60);
+        Clean.render(window, buf);
+
+        let block = Block::bordered()
+            .title(title)
+            .border_style(Style::new().fg(sel_color));
+        let inner = block.inner(window);
+        block.render(window, buf);
+
+        let [query_area, list_area] =
+            Layout::vertical([Constraint::Length(1), Constraint::Fill(1)]).areas(inner);
+        Paragraph::new(Line::from(vec![
+            "> ".fg(sel_color),
+            self.input.content.as_str().into(),
+        ]))
+        .render(query_area, buf);
+        list_area
+    }
+
+    fn render_jump(&mut self, area: Rect, buf: &mut Buffer) {
+        let (sel_color, un_color) = COLORS[self.color_index];
+        let list_area = self.render_prompt_window(area, buf, " Jump to directory ");
+
+        let items = self
+            .frecency
+            .matches
+            .iter()
+            .map(|path| ListItem::new(Line::from(path.to_string_lossy()).fg(un_color)));
+        let list = List::new(items).highlight_style(Style::new().bg(sel_color).fg(un_color));
+        if list.is_empty() {
+            Widget::render(Text::from("No matches").fg(sel_color), list_area, buf);
+        } else {
+            StatefulWidget::render(list, list_area, buf, &mut self.frecency.state);
+        }
+    }
+
+    fn render_goto(&mut self, area: Rect, buf: &mut Buffer) {
+        let (_, un_color) = COLORS[self.color_index];
+        let list_area = self.render_prompt_window(area, buf, " Go to path ");
+
+        let items = self
+            .path_prompt
+            .candidates
+            .iter()
+            .map(|name| ListItem::new(Line::from(name.as_str()).fg(un_color)));
+        Widget::render(List::new(items), list_area, buf);
     }
 
     fn render_sub(&mut self, area: Rect, buf: &mut Buffer, file_struct: &mut FileStruct) {
@@ -244,11 +1169,15 @@ impl FileScout {
         let (sel_color, un_color) = COLORS[self.color_index];
 
         let files = file_struct.next_dir.iter().map(|name| {
-            let value = name
+            let mut value = name
                 .strip_prefix(&file_struct.next)
                 .unwrap()
                 .to_str()
-                .unwrap();
+                .unwrap()
+                .to_string();
+            if let Some(size) = self.sizes.get(name) {
+                value = format!("{}  {}", value, human_size(size));
+            }
             if name.is_dir() {
                 ListItem::new(Line::from(value).fg(sel_color))
             } else {
@@ -268,7 +1197,56 @@ impl FileScout {
 
     fn render_content(&mut self, area: Rect, buf: &mut Buffer, file_struct: &mut FileStruct) {
         let (sel_col, un_col) = COLORS[self.color_index];
-        if !file_struct.content.is_empty() {
+        let stamp = file_struct
+            .content_stamp
+            .as_ref()
+            .filter(|(path, _)| file_struct.current_path.as_ref() == Some(path));
+        if let Some(stamp) = stamp.filter(|(path, _)| is_image(path)) {
+            match self.images.get(stamp) {
+                Some(Ok(picture)) => picture.render(area, buf),
+                Some(Err(error)) => {
+                    Widget::render(Text::from(error).fg(sel_col), area, buf);
+                }
+                None => Widget::render(Text::from("Decoding image...").fg(sel_col), area, buf),
+            }
+            return;
+        }
+        if let Some(large) = file_struct.large.as_mut() {
+            let height = area.height as usize;
+            if large.follow {
+                self.text_scroll_y = large.line_count().saturating_sub(height);
+            }
+            if large.binary {
+                let bytes = large.bytes(self.text_scroll_y, height);
+                self.render_hex(area, buf, &bytes, self.text_scroll_y);
+            } else {
+                let lines: Vec<Line> = large
+                    .lines(self.text_scroll_y, height)
+                    .iter()
+                    .map(|line| Line::from(line.as_str()))
+                    .collect();
+                Paragraph::new(Text::from(lines).fg(un_col))
+                    .scroll((0, self.text_scroll_x as u16))
+                    .render(area, buf);
+            }
+            return;
+        }
+        if let Some(bytes) = &file_struct.binary {
+            let start = (self.text_scroll_y * HEX_WIDTH).min(bytes.len());
+            self.render_hex(area, buf, &bytes[start..], self.text_scroll_y);
+            return;
+        }
+        if self.finder.reveal {
+            self.reveal_match(area, file_struct);
+        }
+        let highlighted = stamp.and_then(|stamp| self.highlighter.get(stamp));
+        if let Some(lines) = highlighted {
+            let start = self.text_scroll_y.min(lines.len());
+            let end = (start + area.height as usize).min(lines.len());
+            Paragraph::new(Text::from(lines[start..end].to_vec()))
+                .scroll((0, self.text_scroll_x as u16))
+                .render(area, buf);
+        } else if !file_struct.content.is_empty() {
             let text = Text::from(file_struct.content.to_string());
             Paragraph::new(text.fg(un_col))
                 .scroll((self.text_scroll_y as u16, self.text_scroll_x as u16))
@@ -276,6 +1254,78 @@ impl FileScout {
         } else {
             Widget::render(Text::from("No content").fg(sel_col), area, buf);
         }
+
+        if self.finder.is_active() {
+            let lines = file_struct.content.lines().skip(self.text_scroll_y);
+            for (row, line) in lines.take(area.height as usize).enumerate() {
+                let matches = self.finder.matches(line);
+                let current = self
+                    .finder
+                    .current
+                    .as_ref()
+                    .filter(|(index, _)| *index == self.text_scroll_y + row)
+                    .map(|(_, range)| range);
+                highlight_matches(buf, area, row, line, &matches, current, self.text_scroll_x);
+            }
+        }
+    }
+
+    fn reveal_match(&mut self, area: Rect, file_struct: &FileStruct) {
+        self.finder.reveal = false;
+        let Some((index, range)) = &self.finder.current else {
+            return;
+        };
+        let Some(line) = file_struct.content_line(*index) else {
+            return;
+        };
+        let height = area.height as usize;
+        if *index < self.text_scroll_y || *index >= self.text_scroll_y + height {
+            self.text_scroll_y = index.saturating_sub(height / 2);
+        }
+        let width = area.width as usize;
+        let start = line[..range.start].width();
+        let end = line[..range.end].width();
+        if end <= width {
+            self.text_scroll_x = 0;
+        } else if start < self.text_scroll_x || end > self.text_scroll_x + width {
+            self.text_scroll_x = start.saturating_sub(width / 4);
+        }
+    }
+
+    fn render_hex(&self, area: Rect, buf: &mut Buffer, bytes: &[u8], first_row: usize) {
+        let (sel_col, un_col) = COLORS[self.color_index];
+        let lines: Vec<Line> = bytes
+            .chunks(HEX_WIDTH)
+            .take(area.height as usize)
+            .enumerate()
+            .map(|(row, chunk)| {
+                let mut hex = String::with_capacity(HEX_WIDTH * 3 + 1);
+                for (index, byte) in chunk.iter().enumerate() {
+                    if index == HEX_WIDTH / 2 {
+                        hex.push(' ');
+                    }
+                    hex.push_str(&format!("{:02x} ", byte));
+                }
+                let ascii: String = chunk
+                    .iter()
+                    .map(|&byte| {
+                        if byte.is_ascii_graphic() || byte == b' ' {
+                            byte as char
+                        } else {
+                            '.'
+                        }
+                    })
+                    .collect();
+                Line::from(vec![
+                    format!("{:08x}  ", (first_row + row) * HEX_WIDTH).fg(sel_col),
+                    format!("{:<width$} ", hex, width = HEX_WIDTH * 3 + 1).fg(un_col),
+                    format!("|{}|", ascii).fg(sel_col),
+                ])
+            })
+            .collect();
+        Paragraph::new(lines)
+            .scroll((0, self.text_scroll_x as u16))
+            .render(area, buf);
     }
 
     fn render_message(&mut self, area: Rect, buf: &mut Buffer, file_struct: &mut FileStruct) {
@@ -291,10 +1341,93 @@ impl FileScout {
         .left_aligned()
         .render(area, buf);
 
-        if let Some(error) = &file_struct.error {
+        let picture = file_struct
+            .content_stamp
+            .as_ref()
+            .and_then(|stamp| self.images.get(stamp))
+            .and_then(|decoded| decoded.ok());
+        if let Some(picture) = picture {
+            Paragraph::new(Text::from(format!(
+                "{} {}x{}",
+                picture.format, picture.width, picture.height
+            )))
+            .centered()
+            .render(area, buf);
+        } else if let Some(format) = &file_struct.text_format {
+            Paragraph::new(Text::from(format.to_string()))
+                .centered()
+                .render(area, buf);
+        }
+
+        let following = file_struct.large.as_ref().is_some_and(|large| large.follow);
+        let hint = match self.mode {
+            ViewMode::MarkSet => Some("mark: press a letter to bookmark this directory"),
+            ViewMode::MarkJump => Some("jump: press a bookmark letter"),
+            ViewMode::ContentView if following => {
+                Some("following appended data, press <F> to stop")
+            }
+            _ => None,
+        };
+        let progress = self
+            .archive_task
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|progress| format!("{} {}%", progress.label, progress.percent()));
+        if let Some(hint) = hint {
+            Paragraph::new(Text::from(hint).bold())
+                .right_aligned()
+                .render(area, buf);
+        } else if let Some(progress) = progress {
+            Paragraph::new(Text::from(progress).bold())
+                .right_aligned()
+                .render(area, buf);
+        } else if let Some(error) = &file_struct.error {
             Paragraph::new(Text::from(error.to_string()).left_aligned().bold())
                 .right_aligned()
                 .render(area, buf);
+        } else if let Some(status) = &file_struct.status {
+            Paragraph::new(Text::from(status.as_str()).left_aligned())
+                .right_aligned()
+                .render(area, buf);
         }
     }
 }
+
+fn highlight_matches(
+    buf: &mut Buffer,
+    area: Rect,
+    row: usize,
+    line: &str,
+    matches: &[Range<usize>],
+    current: Option<&Range<usize>>,
+    scroll: usize,
+) {
+    for range in matches {
+        let style = if Some(range) == current {
+            Style::new().black().on_light_red()
+        } else {
+            Style::new().black().on_yellow()
+        };
+        highlight_range(buf, area, row, line, range.clone(), scroll, style);
+    }
+}
+
+fn highlight_range(
+    buf: &mut Buffer,
+    area: Rect,
+    row: usize,
+    line: &str,
+    range: Range<usize>,
+    scroll: usize,
+    style: Style,
+) {
+    if row >= area.height as usize {
+        return;
+    }
+    let start = line[..range.start].width();
+    let end = line[..range.end].width();
+    for column in start.max(scroll)..end.min(scroll + area.width as usize) {
+        buf[(area.x + (column - scroll) as u16, area.y + row as u16)].set_style(style);
+    }
+}
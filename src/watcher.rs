@@ -0,0 +1,123 @@
+use std::{
+    io::{Error, Result},
+    path::PathBuf,
+    sync::{mpsc, Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use tokio::sync::mpsc::Sender;
+
+use crate::explorer::FileStruct;
+
+const DEBOUNCE: Duration = Duration::from_millis(150);
+const MAX_DELAY: Duration = Duration::from_secs(1);
+
+pub struct DirWatcher {
+    watcher: RecommendedWatcher,
+    requested: Vec<PathBuf>,
+    watched: Vec<PathBuf>,
+    panes: Arc<Mutex<Vec<Arc<Mutex<FileStruct>>>>>,
+    changes: Arc<Mutex<Vec<PathBuf>>>,
+}
+
+impl DirWatcher {
+    pub fn new(tx: Sender<String>) -> Result<Self> {
+        let (event_tx, event_rx) = mpsc::channel::<notify::Result<notify::Event>>();
+        let watcher = notify::recommended_watcher(event_tx).map_err(Error::other)?;
+        let panes: Arc<Mutex<Vec<Arc<Mutex<FileStruct>>>>> = Arc::default();
+
+        let changes = Arc::new(Mutex::new(Vec::new()));
+
+        let refresh_panes = Arc::clone(&panes);
+        let changed = Arc::clone(&changes);
+        thread::spawn(move || {
+            while let Ok(event) = event_rx.recv() {
+                if !is_change(&event) {
+                    continue;
+                }
+                let mut paths = changed_paths(event);
+                let burst = Instant::now();
+                while burst.elapsed() < MAX_DELAY {
+                    match event_rx.recv_timeout(DEBOUNCE) {
+                        Ok(event) if is_change(&event) => paths.extend(changed_paths(event)),
+                        Ok(_) => {}
+                        Err(_) => break,
+                    }
+                }
+                changed.lock().unwrap().extend(paths);
+                let panes = refresh_panes.lock().unwrap().clone();
+                for files in panes {
+                    files.lock().unwrap().refresh();
+                }
+                let _ = tx.try_send(String::new());
+            }
+        });
+
+        Ok(Self {
+            watcher,
+            requested: Vec::new(),
+            watched: Vec::new(),
+            panes,
+            changes,
+        })
+    }
+
+    /// Paths changed since the last call, for caches derived from their contents.
+    pub fn take_changes(&self) -> Vec<PathBuf> {
+        std::mem::take(&mut *self.changes.lock().unwrap())
+    }
+
+    /// Watches the directories shown by `panes` and refreshes them all on changes.
+    pub fn sync(&mut self, panes: &[Arc<Mutex<FileStruct>>]) {
+        let mut wanted = Vec::new();
+        for files in panes {
+            let file_struct = files.lock().unwrap();
+            let mut shown = vec![file_struct.pwd.to_path_buf()];
+            if file_struct.parent != file_struct.pwd {
+                shown.push(file_struct.parent.to_path_buf());
+            }
+            if let Some(path) = &file_struct.current_path {
+                if path.is_dir() {
+                    shown.push(path.to_path_buf());
+                }
+            }
+            for path in shown {
+                if !wanted.contains(&path) {
+                    wanted.push(path);
+                }
+            }
+        }
+        *self.panes.lock().unwrap() = panes.to_vec();
+
+        // Compared against the requested paths so a failing watch isn't retried every tick.
+        if wanted == self.requested {
+            return;
+        }
+        for path in self.watched.drain(..) {
+            let _ = self.watcher.unwatch(&path);
+        }
+        self.requested = wanted.clone();
+        for path in wanted {
+            if self
+                .watcher
+                .watch(&path, RecursiveMode::NonRecursive)
+                .is_ok()
+            {
+                self.watched.push(path);
+            }
+        }
+    }
+}
+
+fn changed_paths(event: notify::Result<notify::Event>) -> Vec<PathBuf> {
+    event.map(|event| event.paths).unwrap_or_default()
+}
+
+fn is_change(event: &notify::Result<notify::Event>) -> bool {
+    match event {
+        Ok(event) => !matches!(event.kind, EventKind::Access(_)),
+        Err(_) => false,
+    }
+}